@@ -6,7 +6,17 @@ const $output = document.getElementById("output") as HTMLPreElement;
 
 $input.addEventListener("input", () => {
   const input = $input.value;
-  const { chance_ai, chance_human, metrics } = predict(input);
+
+  let prediction;
+  try {
+    prediction = predict(input);
+  } catch (err) {
+    // the model can't score anything, e.g. it needs embeddings
+    $output.innerText = `Couldn't score the text: ${err instanceof Error ? err.message : err}`;
+    return;
+  }
+
+  const { chance_ai, chance_human, metrics } = prediction;
 
   $output.innerText = `Text is most likely ${chance_ai >= chance_human ? "AI" : "Human"}
 
@@ -18,8 +28,10 @@ Non-zero metrics:
 ${display(metrics)}`;
 });
 
-function display(metrics: Record<string, number>): string {
+// optional blocks are null or nested, only the flat numbers are shown
+function display(metrics: Record<string, unknown>): string {
   const output = Object.entries(metrics)
+    .filter((entry): entry is [string, number] => typeof entry[1] === "number")
     .filter(([, value]) => value !== 0)
     .sort(([, a], [, b]) => b - a)
     .map(([key, value]) =>
@@ -1,3 +1,4 @@
 /* tslint:disable */
 /* eslint-disable */
+
 export function predict(devlog: string): any;
@@ -1,4 +1,9 @@
+/* @ts-self-types="./sonai.d.ts" */
 import * as wasm from "./sonai_bg.wasm";
-export * from "./sonai_bg.js";
 import { __wbg_set_wasm } from "./sonai_bg.js";
-__wbg_set_wasm(wasm);
\ No newline at end of file
+
+__wbg_set_wasm(wasm);
+
+export {
+    predict
+} from "./sonai_bg.js";
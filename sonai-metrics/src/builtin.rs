@@ -0,0 +1,556 @@
+use aho_corasick::{AhoCorasick, BuildError};
+use pulldown_cmark::{Event, Parser, Tag};
+use unicode_script::{Script, UnicodeScript};
+
+use crate::metric::{Document, Metric, MetricRegistry};
+use crate::normalize::is_invisible;
+
+impl MetricRegistry {
+    /// The metrics every model is trained on, in the legacy column order.
+    pub fn builtin() -> Result<Self, BuildError> {
+        let mut registry = Self::new();
+
+        registry.register(Emoji);
+        registry.register(Buzzwords {
+            buzzwords: AhoCorasick::new([
+                "the app",
+                "-powered",
+                "-melting",
+                "powered by",
+                "based on",
+                "-like",
+                "todo app",
+                "interactive cards",
+                "modern",
+                "delivers",
+                "delivers both",
+                "across all devices",
+                "style and usability",
+                "real-time",
+                "calm, reflective space",
+                "simulate",
+                "self-care",
+                "meaningful",
+                "user interaction",
+                "digital wellness",
+                "user-friendly interface",
+                "responsive",
+                "auto-typing",
+                "engagement",
+                "community",
+                "ambitious goal",
+                "world of data",
+                "programming toolkit",
+                "summer of learning",
+                "and a custom",
+                "foundational principles",
+                "began to wonder",
+                "i'm announcing",
+                "fully featured",
+                "next.js 13",
+                "next.js 14",
+                "next.js 13/14",
+                "svelte 4",
+                "app router",
+                "modern",
+                "web dashboard",
+                "step-by-step",
+                "excited",
+                "build this",
+                "inner workings",
+                "live code editor",
+                "new project",
+                "kicking off",
+                "lightweight",
+                "in the browser",
+                "brutalism",
+                "morphism",
+                "comprehensive",
+                "philosophy",
+                "revolutionary",
+                "wisdom",
+                "leetcode",
+                "global accessibility",
+                "developers",
+                "harmony of tradition and innovation",
+                "intuitive",
+                "powerful features",
+                "cross-platform",
+                "inspiration",
+                "technical architecture",
+                "users can",
+                "rewarding feel",
+                "progress tracking",
+                "understandable",
+                "digital co-pilot",
+                "significantly improves usability",
+                "easier to navigate",
+                "react for the frontend",
+                "stylish",
+                "mobile-",
+                "ui/ux",
+                "the single solution",
+                "fully customizable",
+                "about to change everything",
+                "solved that problem",
+                "the same tech behind",
+                "lives in its own",
+                "is like a",
+                "kubernetes",
+                "orchestrated",
+                "microservices architecture",
+                "corporate jargon",
+                "✨", // This emoji sucks
+                "buttery-smooth",
+                "biggest competitor",
+                "it lets you",
+            ])?,
+            negative: AhoCorasick::new(["modern english", "made the app"])?,
+        });
+        registry.register(CharClass {
+            name: "irr_dash",
+            scale: 20.,
+            matches: |c, next| match c {
+                '–' | '—' | '‒' | '―' | '⸻' | '⸺' | '−' | '﹘' | '－' | '‑' | '‐' | '᠆' | '־'
+                | '֊' => true,
+                '-' => next.is_some_and(|x| !x.is_whitespace()),
+                _ => false,
+            },
+        });
+        registry.register(CharClass {
+            name: "irr_quote",
+            scale: 5.,
+            matches: |c, _| matches!(c, '“' | '”' | '‘' | '’'),
+        });
+        registry.register(Labels);
+        // tradeoff is fine for a match list this small
+        registry.register(Lexicon {
+            name: "irr_ell",
+            scale: 1.,
+            source: Source::Raw,
+            automaton: AhoCorasick::new(["…", "..."])?,
+        });
+        registry.register(Lexicon {
+            name: "html",
+            scale: 5.,
+            source: Source::Flat,
+            automaton: AhoCorasick::new(["&amp;"])?,
+        });
+        registry.register(Lexicon {
+            name: "not_just",
+            scale: 5.,
+            source: Source::Flat,
+            automaton: AhoCorasick::new([
+                "more than just",
+                "isn't a",
+                "this isn't a prototype",
+                "isn't just a",
+                "it's not just",
+                "i'm not just",
+                "it's just not",
+                "i'm just not",
+                "isn't just",
+                "didn't just",
+                "more than a",
+                "it's more",
+            ])?,
+        });
+        registry.register(Lexicon {
+            name: "devlog",
+            scale: 1.,
+            source: Source::Flat,
+            automaton: AhoCorasick::new([
+                "dev log",
+                "dev-log",
+                "day",
+                "devlog #",
+                "dev log #",
+                "dev-log #",
+                "day #",
+                "first devlog",
+                "today,",
+                "june ",
+                "july ",
+                "august ",
+                "jun ",
+                "jul ",
+                "aug ",
+                "-06-",
+                "-07-",
+                "-08-",
+                "/06/",
+                "/07/",
+                "/08/",
+                ".06.",
+                ".07.",
+                ".08.",
+                "/6/",
+                "/7/",
+                "/8/",
+                "this week was all about",
+                "the project",
+                "what's next",
+                "next steps",
+                "why it matters",
+                "more coming soon",
+                "what i built",
+            ])?,
+        });
+        registry.register(Markdown);
+        registry.register(Hashtags);
+        registry.register(Lexicon {
+            name: "fancy",
+            scale: 1.,
+            source: Source::Flat,
+            automaton: AhoCorasick::new(["(e.g.", "(formerly"])?,
+        });
+        registry.register(Lexicon {
+            name: "bad_per",
+            scale: 1.,
+            source: Source::Flat,
+            automaton: AhoCorasick::new([
+                " we're ",
+                " we ",
+                " they're ",
+                " us ",
+                " our ",
+                " ours ",
+                " ourselves ",
+                " them ",
+                " people ",
+                " theirs ",
+                " themselves ",
+                " oneself ",
+            ])?,
+        });
+        registry.register(Lexicon {
+            name: "backstory",
+            scale: 1.,
+            source: Source::Flat,
+            automaton: AhoCorasick::new([
+                "as a",
+                "high school student",
+                "middle school student",
+                "preparing for",
+                "exams",
+                "was born from",
+                "personal frustration",
+                "makes it unique",
+                "and eventually",
+                "the intention",
+                "it's been a journey",
+                "a journey",
+                "it's all about",
+                "leverage that knowledge",
+                "dive into",
+                "become a versatile programmer",
+                "my adventure",
+                "foundational principles",
+                "how computers truly work",
+                "the world of data",
+                "an ambitious goal",
+                "excited to build",
+                "programming toolkit",
+                "summer of learning",
+                "something insane",
+                "think of it like",
+                "drowning in",
+                "last week",
+                "next week",
+            ])?,
+        });
+        registry.register(CharClass {
+            name: "irr_arr",
+            scale: 20.,
+            matches: |c, _| {
+                matches!(
+                    c,
+                    '→' | '↑' | '↓' | '↔' | '↕' | '⇒' | '⇐' | '⇑' | '⇓' | '➔' | '➜'
+                )
+            },
+        });
+        // zero-width, nbsp, soft hyphens, bidi controls
+        registry.register(CharClass {
+            name: "invisible",
+            scale: 5.,
+            matches: |c, _| {
+                matches!(
+                    c,
+                    '\u{00A0}' | '\u{2000}'..='\u{200A}' | '\u{202F}' | '\u{205F}' | '\u{3000}'
+                ) || is_invisible(c)
+            },
+        });
+        registry.register(Homoglyphs);
+
+        Ok(registry)
+    }
+}
+
+/// Emoji / sentences
+struct Emoji;
+
+impl Metric for Emoji {
+    fn name(&self) -> &'static str {
+        "emoji"
+    }
+
+    fn compute(&self, doc: &Document) -> f64 {
+        doc.emoji().count() as f64 / doc.legacy_sentence_count() as f64
+    }
+
+    fn scale(&self) -> f64 {
+        10.
+    }
+}
+
+/// Buzzwords / sentences
+struct Buzzwords {
+    buzzwords: AhoCorasick,
+    negative: AhoCorasick,
+}
+
+impl Metric for Buzzwords {
+    fn name(&self) -> &'static str {
+        "buzzword"
+    }
+
+    fn compute(&self, doc: &Document) -> f64 {
+        let buzzwords = self
+            .buzzwords
+            .find_iter(&doc.flat)
+            .count()
+            .saturating_sub(self.negative.find_iter(&doc.flat).count());
+
+        buzzwords as f64 / doc.legacy_sentence_count() as f64
+    }
+
+    fn scale(&self) -> f64 {
+        20.
+    }
+}
+
+enum Source {
+    Raw,
+    Flat,
+}
+
+/// Pattern matches, either on what was typed or on the normalized text.
+struct Lexicon {
+    name: &'static str,
+    scale: f64,
+    source: Source,
+    automaton: AhoCorasick,
+}
+
+impl Metric for Lexicon {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn compute(&self, doc: &Document) -> f64 {
+        let haystack = match self.source {
+            Source::Raw => doc.raw,
+            Source::Flat => &doc.flat,
+        };
+
+        self.automaton.find_iter(haystack).count() as f64
+    }
+
+    fn scale(&self) -> f64 {
+        self.scale
+    }
+}
+
+/// Raw chars outside emoji, `matches` also sees the next char in the grapheme.
+struct CharClass {
+    name: &'static str,
+    scale: f64,
+    matches: fn(char, Option<char>) -> bool,
+}
+
+impl Metric for CharClass {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn compute(&self, doc: &Document) -> f64 {
+        doc.chars()
+            .filter(|&(c, next)| (self.matches)(c, next))
+            .count() as f64
+    }
+
+    fn scale(&self) -> f64 {
+        self.scale
+    }
+}
+
+/// Lines starting with `Some Label:`
+struct Labels;
+
+impl Metric for Labels {
+    fn name(&self) -> &'static str {
+        "labels"
+    }
+
+    fn compute(&self, doc: &Document) -> f64 {
+        doc.text
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .filter(|(label, _)| {
+                let label = label.trim();
+
+                !label.is_empty()
+                    && label
+                        .chars()
+                        .all(|c| c.is_alphabetic() || c.is_whitespace())
+            })
+            .count() as f64
+    }
+}
+
+struct Hashtags;
+
+impl Metric for Hashtags {
+    fn name(&self) -> &'static str {
+        "hashtags"
+    }
+
+    fn compute(&self, doc: &Document) -> f64 {
+        doc.flat
+            .split_whitespace()
+            .filter(|word| word.starts_with('#') && word.len() > 1)
+            .count() as f64
+    }
+}
+
+/// Markdown syntax a devlog has no business using
+struct Markdown;
+
+impl Metric for Markdown {
+    fn name(&self) -> &'static str {
+        "irr_md"
+    }
+
+    fn compute(&self, doc: &Document) -> f64 {
+        let markdown = Parser::new(doc.raw)
+            .filter(|event| {
+                matches!(
+                    event,
+                    Event::InlineMath(_)
+                        | Event::DisplayMath(_)
+                        | Event::Html(_)
+                        | Event::FootnoteReference(_)
+                        | Event::TaskListMarker(_)
+                        | Event::Rule
+                        | Event::InlineHtml(_)
+                        | Event::Start(
+                            Tag::BlockQuote(_)
+                                | Tag::CodeBlock(_)
+                                | Tag::FootnoteDefinition(_)
+                                | Tag::Emphasis
+                                | Tag::Subscript
+                                | Tag::Superscript
+                                | Tag::Strong
+                                | Tag::Strikethrough
+                                | Tag::Heading { .. }
+                                | Tag::Link { .. }
+                                | Tag::Image { .. }
+                        )
+                )
+            })
+            .count()
+            + doc.raw.matches('•').count(); // Lists are OK, this shit is not
+
+        markdown as f64
+    }
+}
+
+/// Words mixing latin with cyrillic/greek look-alikes
+struct Homoglyphs;
+
+impl Metric for Homoglyphs {
+    fn name(&self) -> &'static str {
+        "homoglyph"
+    }
+
+    fn compute(&self, doc: &Document) -> f64 {
+        doc.raw
+            .split_whitespace()
+            .filter(|word| {
+                let mut scripts = word.chars().filter_map(|c| match c.script() {
+                    script @ (Script::Latin | Script::Cyrillic | Script::Greek) => Some(script),
+                    _ => None,
+                });
+
+                scripts
+                    .next()
+                    .is_some_and(|first| scripts.any(|script| script != first))
+            })
+            .count() as f64
+    }
+
+    fn scale(&self) -> f64 {
+        5.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metric(name: &str, text: &str) -> f64 {
+        let registry = MetricRegistry::builtin().unwrap();
+
+        registry.compute(&Document::new(text)).get(name).unwrap()
+    }
+
+    fn buzzwords(text: &str) -> f64 {
+        metric("buzzword", text)
+    }
+
+    #[test]
+    fn spoofed_buzzwords_match() {
+        assert_eq!(buzzwords("modern and \u{039C}\u{039F}DERN"), 2.);
+        assert_eq!(buzzwords("ＭＯＤＥＲＮ and modern"), 2.);
+    }
+
+    #[test]
+    fn legacy_rates_keep_the_legacy_sentence_split() {
+        // one sentence to unicode_sentences, three split on '.'
+        let text = "v1.2.3 is out \u{1F680}";
+        assert_eq!(Document::new(text).sentence_count(), 1);
+
+        assert_eq!(metric("emoji", text), 1. / 3.);
+        assert_eq!(buzzwords("v1.2.3 is modern"), 1. / 3.);
+    }
+
+    #[test]
+    fn invisible_counts_joiners_and_bidi_marks() {
+        assert_eq!(metric("invisible", "plain text"), 0.);
+        assert_eq!(metric("invisible", "zero\u{200B}width jo\u{200D}iner"), 2.);
+        assert_eq!(
+            metric(
+                "invisible",
+                "\u{202E}reversed\u{202C} \u{2066}isolate\u{2069}"
+            ),
+            4.
+        );
+        assert_eq!(metric("invisible", "no\u{00A0}break"), 1.);
+        // the joiner inside an emoji sequence is part of the emoji
+        assert_eq!(metric("invisible", "coding \u{1F469}\u{200D}\u{1F4BB}"), 0.);
+    }
+
+    #[test]
+    fn homoglyph_counts_mixed_script_words() {
+        // cyrillic а and greek Ο inside latin words
+        assert_eq!(metric("homoglyph", "p\u{0430}ypal and G\u{039F}OGLE"), 2.);
+        assert_eq!(
+            metric(
+                "homoglyph",
+                "\u{043F}\u{0440}\u{0438}\u{0432}\u{0435}\u{0442} \u{03B1}\u{03B2}\u{03B3}"
+            ),
+            0.
+        );
+        assert_eq!(metric("homoglyph", "plain latin words"), 0.);
+    }
+}
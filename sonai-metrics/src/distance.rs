@@ -0,0 +1,258 @@
+use linfa_nn::distance::Distance;
+use ndarray::{Array1, Array2, ArrayView, ArrayView1, ArrayView2, Axis, Dimension};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+// keeps the covariance invertible when columns are constant or collinear
+const RIDGE: f64 = 1e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DistanceKind {
+    /// Largest single column difference, the original choice
+    #[default]
+    LInf,
+    L1,
+    L2,
+    Cosine,
+    Mahalanobis,
+}
+
+impl DistanceKind {
+    pub const ALL: [Self; 5] = [
+        Self::LInf,
+        Self::L1,
+        Self::L2,
+        Self::Cosine,
+        Self::Mahalanobis,
+    ];
+}
+
+impl FromStr for DistanceKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linf" => Ok(Self::LInf),
+            "l1" => Ok(Self::L1),
+            "l2" => Ok(Self::L2),
+            "cosine" => Ok(Self::Cosine),
+            "mahalanobis" => Ok(Self::Mahalanobis),
+            _ => Err(format!(
+                "unknown distance `{s}`, expected linf, l1, l2, cosine or mahalanobis"
+            )),
+        }
+    }
+}
+
+/// Distance the clusters are fitted and scored with, chosen at training time
+/// and stored in the model.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum DistanceFunction {
+    #[default]
+    LInf,
+    L1,
+    L2,
+    /// 1 - cosine similarity
+    Cosine,
+    Mahalanobis {
+        inverse_covariance: Array2<f64>,
+    },
+}
+
+impl DistanceFunction {
+    /// Only Mahalanobis learns anything from `features`.
+    pub fn fit(kind: DistanceKind, features: ArrayView2<f64>) -> Self {
+        match kind {
+            DistanceKind::LInf => Self::LInf,
+            DistanceKind::L1 => Self::L1,
+            DistanceKind::L2 => Self::L2,
+            DistanceKind::Cosine => Self::Cosine,
+            DistanceKind::Mahalanobis => Self::Mahalanobis {
+                inverse_covariance: inverse(covariance(features)),
+            },
+        }
+    }
+
+    pub fn kind(&self) -> DistanceKind {
+        match self {
+            Self::LInf => DistanceKind::LInf,
+            Self::L1 => DistanceKind::L1,
+            Self::L2 => DistanceKind::L2,
+            Self::Cosine => DistanceKind::Cosine,
+            Self::Mahalanobis { .. } => DistanceKind::Mahalanobis,
+        }
+    }
+}
+
+impl Distance<f64> for DistanceFunction {
+    fn distance<D: Dimension>(&self, a: ArrayView<f64, D>, b: ArrayView<f64, D>) -> f64 {
+        let pairs = a.iter().zip(b.iter());
+
+        match self {
+            Self::LInf => pairs.map(|(x, y)| (x - y).abs()).fold(0., f64::max),
+            Self::L1 => pairs.map(|(x, y)| (x - y).abs()).sum(),
+            Self::L2 => pairs.map(|(x, y)| (x - y).powi(2)).sum::<f64>().sqrt(),
+            Self::Cosine => {
+                let (dot, aa, bb) = pairs.fold((0., 0., 0.), |(dot, aa, bb), (x, y)| {
+                    (dot + x * y, aa + x * x, bb + y * y)
+                });
+
+                // the empty devlog sits at the origin, call it orthogonal
+                if aa == 0. || bb == 0. {
+                    1.
+                } else {
+                    1. - dot / (aa.sqrt() * bb.sqrt())
+                }
+            }
+            Self::Mahalanobis { inverse_covariance } => {
+                let diff: Vec<f64> = pairs.map(|(x, y)| x - y).collect();
+
+                // linfa also measures whole centroid matrices to check convergence,
+                // add up the rows then
+                diff.chunks(inverse_covariance.nrows().max(1))
+                    .map(|row| {
+                        let row = ArrayView1::from(row);
+                        row.dot(&inverse_covariance.dot(&row))
+                    })
+                    .sum::<f64>()
+                    .max(0.)
+                    .sqrt()
+            }
+        }
+    }
+}
+
+fn covariance(features: ArrayView2<f64>) -> Array2<f64> {
+    let (n, d) = features.dim();
+
+    let mean = features
+        .mean_axis(Axis(0))
+        .unwrap_or_else(|| Array1::zeros(d));
+    let centered = &features - &mean;
+    let mut covariance = centered.t().dot(&centered) / (n.max(2) - 1) as f64;
+
+    let ridge = RIDGE * (covariance.diag().sum() / d.max(1) as f64).max(1.);
+    covariance.diag_mut().mapv_inplace(|x| x + ridge);
+
+    covariance
+}
+
+/// Gauss-Jordan with partial pivoting, the matrix is symmetric positive
+/// definite after the ridge so this never hits a zero pivot.
+fn inverse(mut matrix: Array2<f64>) -> Array2<f64> {
+    let d = matrix.nrows();
+    let mut inverse = Array2::eye(d);
+
+    for col in 0..d {
+        let pivot = (col..d)
+            .max_by(|&i, &j| matrix[[i, col]].abs().total_cmp(&matrix[[j, col]].abs()))
+            .expect("col < d");
+
+        for k in 0..d {
+            matrix.swap([col, k], [pivot, k]);
+            inverse.swap([col, k], [pivot, k]);
+        }
+
+        let scale = matrix[[col, col]];
+        matrix.row_mut(col).mapv_inplace(|x| x / scale);
+        inverse.row_mut(col).mapv_inplace(|x| x / scale);
+
+        for row in 0..d {
+            if row == col {
+                continue;
+            }
+
+            let factor = matrix[[row, col]];
+            if factor == 0. {
+                continue;
+            }
+
+            for k in 0..d {
+                matrix[[row, k]] -= factor * matrix[[col, k]];
+                inverse[[row, k]] -= factor * inverse[[col, k]];
+            }
+        }
+    }
+
+    inverse
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-6, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn inverse_of_a_known_matrix() {
+        let matrix = array![[4., 1., 2.], [1., 3., 0.5], [2., 0.5, 5.]];
+        let product = matrix.dot(&inverse(matrix.clone()));
+
+        for ((i, j), &value) in product.indexed_iter() {
+            assert_close(value, if i == j { 1. } else { 0. });
+        }
+
+        // needs the row swaps
+        let swapped = inverse(array![[0., 2.], [4., 0.]]);
+        assert_eq!(swapped, array![[0., 0.25], [0.5, 0.]]);
+    }
+
+    #[test]
+    fn mahalanobis_divides_out_the_spread() {
+        // an uncorrelated grid, much wider along column 0
+        let features = Array2::from_shape_fn((200, 2), |(i, j)| {
+            let x = (i % 20) as f64 - 9.5;
+            let y = (i / 20) as f64 - 4.5;
+
+            [10. * x, 2. * y][j]
+        });
+        let distance = DistanceFunction::fit(DistanceKind::Mahalanobis, features.view());
+
+        let across = distance.distance(array![0., 0.].view(), array![10., 0.].view());
+        let along = distance.distance(array![0., 0.].view(), array![0., 1.].view());
+
+        let spread = covariance(features.view());
+        assert_close(across, 10. / spread[[0, 0]].sqrt());
+        assert_close(along, 1. / spread[[1, 1]].sqrt());
+    }
+
+    #[test]
+    fn identity_mahalanobis_is_l2() {
+        let distance = DistanceFunction::Mahalanobis {
+            inverse_covariance: Array2::eye(3),
+        };
+        let (a, b) = (array![1., -2., 3.], array![0.5, 4., -1.]);
+
+        assert_close(
+            distance.distance(a.view(), b.view()),
+            DistanceFunction::L2.distance(a.view(), b.view()),
+        );
+
+        // whole centroid matrices add up per row
+        let (a, b) = (array![[1., 0., 0.], [0., 2., 0.]], Array2::zeros((2, 3)));
+        assert_close(distance.distance(a.view(), b.view()), 5f64.sqrt());
+    }
+
+    #[test]
+    fn constant_and_collinear_columns_invert() {
+        let features = Array2::from_shape_fn((50, 3), |(i, j)| match j {
+            0 => i as f64,
+            1 => 2. * i as f64,
+            _ => 7.,
+        });
+        let DistanceFunction::Mahalanobis { inverse_covariance } =
+            DistanceFunction::fit(DistanceKind::Mahalanobis, features.view())
+        else {
+            unreachable!()
+        };
+
+        assert!(inverse_covariance.iter().all(|x| x.is_finite()));
+
+        let distance = DistanceFunction::Mahalanobis { inverse_covariance };
+        let d = distance.distance(features.row(0), features.row(49));
+        assert!(d.is_finite() && d > 0.);
+    }
+}
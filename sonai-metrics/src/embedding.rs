@@ -0,0 +1,123 @@
+use candle_core::{Device, Error, Result, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{BertModel, Config, DTYPE};
+use ndarray::Array2;
+use std::fmt;
+use std::path::Path;
+use tokenizers::{PaddingParams, Tokenizer, TruncationParams};
+
+/// Local BERT-style sentence embedder (MiniLM and friends) running on the CPU.
+pub struct Embedder {
+    model: BertModel,
+    tokenizer: Tokenizer,
+    device: Device,
+}
+
+impl fmt::Debug for Embedder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Embedder").finish_non_exhaustive()
+    }
+}
+
+impl Embedder {
+    /// Loads `config.json`, `tokenizer.json` and `model.safetensors` from `dir`.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let device = Device::Cpu;
+
+        let config: Config =
+            serde_json::from_str(&std::fs::read_to_string(dir.join("config.json"))?)
+                .map_err(Error::wrap)?;
+
+        let mut tokenizer = Tokenizer::from_file(dir.join("tokenizer.json")).map_err(Error::msg)?;
+        tokenizer.with_padding(Some(PaddingParams::default()));
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: config.max_position_embeddings,
+                ..Default::default()
+            }))
+            .map_err(Error::msg)?;
+
+        let weights = std::fs::read(dir.join("model.safetensors"))?;
+        let vb = VarBuilder::from_buffered_safetensors(weights, DTYPE, &device)?;
+        let model = BertModel::load(vb, &config)?;
+
+        Ok(Self {
+            model,
+            tokenizer,
+            device,
+        })
+    }
+
+    /// Mean pooled, L2 normalized embeddings, one row per text.
+    pub fn embed(&self, texts: &[&str]) -> Result<Array2<f64>> {
+        let encodings = self
+            .tokenizer
+            .encode_batch(texts.to_vec(), true)
+            .map_err(Error::msg)?;
+
+        let ids = encodings
+            .iter()
+            .map(|encoding| Tensor::new(encoding.get_ids(), &self.device))
+            .collect::<Result<Vec<_>>>()?;
+        let mask = encodings
+            .iter()
+            .map(|encoding| Tensor::new(encoding.get_attention_mask(), &self.device))
+            .collect::<Result<Vec<_>>>()?;
+
+        let ids = Tensor::stack(&ids, 0)?;
+        let mask = Tensor::stack(&mask, 0)?;
+
+        let hidden = self.model.forward(&ids, &ids.zeros_like()?, Some(&mask))?;
+
+        // average only over real tokens, padding would drag short texts together
+        let mask = mask.to_dtype(DTYPE)?.unsqueeze(2)?;
+        let pooled = hidden
+            .broadcast_mul(&mask)?
+            .sum(1)?
+            .broadcast_div(&mask.sum(1)?)?;
+        let pooled = pooled.broadcast_div(&pooled.sqr()?.sum_keepdim(1)?.sqrt()?)?;
+
+        let rows: Vec<Vec<f32>> = pooled.to_vec2()?;
+        let dims = rows.first().map_or(0, Vec::len);
+
+        Ok(Array2::from_shape_fn((rows.len(), dims), |(i, j)| {
+            rows[i][j] as f64
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tiny() -> Embedder {
+        Embedder::load(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/tiny-bert")).unwrap()
+    }
+
+    #[test]
+    fn embeds_unit_rows() {
+        let embeddings = tiny()
+            .embed(&["a short devlog.", "another, much longer one!"])
+            .unwrap();
+
+        assert_eq!(embeddings.dim(), (2, 8));
+        for row in embeddings.rows() {
+            assert!((row.dot(&row) - 1.).abs() < 1e-5);
+        }
+        assert_ne!(embeddings.row(0), embeddings.row(1));
+    }
+
+    #[test]
+    fn padding_doesnt_change_embeddings() {
+        let embedder = tiny();
+        let alone = embedder.embed(&["hi"]).unwrap();
+        let batched = embedder
+            .embed(&["hi", "a much longer text than hi"])
+            .unwrap();
+
+        for (a, b) in alone.row(0).iter().zip(batched.row(0)) {
+            assert!((a - b).abs() < 1e-5);
+        }
+    }
+}
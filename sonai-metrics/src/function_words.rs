@@ -0,0 +1,283 @@
+/// Default English function words for [`crate::FeatureSet::function_words`],
+/// plus the filler adverbs people actually type in devlogs.
+pub const FUNCTION_WORDS: &[&str] = &[
+    // articles and determiners
+    "a",
+    "an",
+    "the",
+    "this",
+    "that",
+    "these",
+    "those",
+    "each",
+    "every",
+    "either",
+    "neither",
+    "some",
+    "any",
+    "no",
+    "all",
+    "both",
+    "few",
+    "many",
+    "much",
+    "more",
+    "most",
+    "less",
+    "least",
+    "several",
+    "such",
+    "own",
+    "other",
+    "another",
+    "same",
+    "enough",
+    // pronouns
+    "i",
+    "me",
+    "my",
+    "mine",
+    "myself",
+    "you",
+    "your",
+    "yours",
+    "yourself",
+    "he",
+    "him",
+    "his",
+    "himself",
+    "she",
+    "her",
+    "hers",
+    "herself",
+    "it",
+    "its",
+    "itself",
+    "we",
+    "us",
+    "our",
+    "ours",
+    "ourselves",
+    "they",
+    "them",
+    "their",
+    "theirs",
+    "themselves",
+    "who",
+    "whom",
+    "whose",
+    "which",
+    "what",
+    "whatever",
+    "whoever",
+    "someone",
+    "something",
+    "anyone",
+    "anything",
+    "everyone",
+    "everything",
+    "nobody",
+    "nothing",
+    "one",
+    // prepositions
+    "about",
+    "above",
+    "across",
+    "after",
+    "against",
+    "along",
+    "among",
+    "around",
+    "at",
+    "before",
+    "behind",
+    "below",
+    "beneath",
+    "beside",
+    "between",
+    "beyond",
+    "by",
+    "despite",
+    "down",
+    "during",
+    "except",
+    "for",
+    "from",
+    "in",
+    "inside",
+    "into",
+    "like",
+    "near",
+    "of",
+    "off",
+    "on",
+    "onto",
+    "out",
+    "outside",
+    "over",
+    "past",
+    "since",
+    "through",
+    "throughout",
+    "till",
+    "to",
+    "toward",
+    "towards",
+    "under",
+    "until",
+    "up",
+    "upon",
+    "via",
+    "with",
+    "within",
+    "without",
+    // conjunctions
+    "and",
+    "but",
+    "or",
+    "nor",
+    "so",
+    "yet",
+    "because",
+    "although",
+    "though",
+    "while",
+    "whereas",
+    "if",
+    "unless",
+    "whether",
+    "than",
+    "as",
+    "once",
+    "when",
+    "whenever",
+    "where",
+    "wherever",
+    // auxiliaries and modals
+    "am",
+    "is",
+    "are",
+    "was",
+    "were",
+    "be",
+    "been",
+    "being",
+    "have",
+    "has",
+    "had",
+    "having",
+    "do",
+    "does",
+    "did",
+    "doing",
+    "can",
+    "could",
+    "may",
+    "might",
+    "must",
+    "shall",
+    "should",
+    "will",
+    "would",
+    "ought",
+    // adverbs and particles
+    "not",
+    "very",
+    "too",
+    "also",
+    "just",
+    "only",
+    "even",
+    "still",
+    "already",
+    "again",
+    "ever",
+    "never",
+    "always",
+    "often",
+    "sometimes",
+    "then",
+    "there",
+    "here",
+    "now",
+    "how",
+    "why",
+    "quite",
+    "rather",
+    "almost",
+    "perhaps",
+    "maybe",
+    "however",
+    "therefore",
+    "thus",
+    "instead",
+    "indeed",
+    "really",
+    "basically",
+    "actually",
+    "literally",
+    "honestly",
+    "pretty",
+    "kinda",
+    "probably",
+    "definitely",
+    "simply",
+    "seamlessly",
+    "truly",
+    "finally",
+    "currently",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FeatureSet, TextMetricFactory, function_word_features};
+
+    fn frequencies(words: &[&str], text: &str) -> Vec<f64> {
+        let factory = TextMetricFactory::new().unwrap().with_features(FeatureSet {
+            function_words: Some(words.iter().map(|word| word.to_string()).collect()),
+            ..FeatureSet::default()
+        });
+
+        factory.calculate(text).unwrap().function_words.unwrap()
+    }
+
+    #[test]
+    fn rates_are_per_word() {
+        let frequencies = frequencies(FUNCTION_WORDS, "The cat and the dog sat on a mat");
+        let rate = |word| frequencies[FUNCTION_WORDS.iter().position(|w| *w == word).unwrap()];
+
+        assert_eq!(frequencies.len(), FUNCTION_WORDS.len());
+        assert_eq!(rate("the"), 2. / 9.);
+        assert_eq!(rate("and"), 1. / 9.);
+        assert_eq!(rate("on"), 1. / 9.);
+        assert_eq!(rate("a"), 1. / 9.);
+        assert_eq!(frequencies.iter().sum::<f64>(), 5. / 9.);
+    }
+
+    #[test]
+    fn columns_follow_the_list() {
+        let text = "the plan is on the board";
+        let metrics = TextMetricFactory::new()
+            .unwrap()
+            .with_features(FeatureSet {
+                function_words: Some(FUNCTION_WORDS.iter().map(|w| w.to_string()).collect()),
+                ..FeatureSet::default()
+            })
+            .calculate(text)
+            .unwrap();
+        let columns = function_word_features(&[&metrics], FUNCTION_WORDS.len());
+
+        for (j, word) in FUNCTION_WORDS.iter().enumerate() {
+            let expected = match *word {
+                "the" => 2. / 6.,
+                "is" | "on" => 1. / 6.,
+                _ => 0.,
+            };
+            assert_eq!(columns[[0, j]], expected, "{word}");
+        }
+
+        assert_eq!(frequencies(&["on", "the"], text), [1. / 6., 2. / 6.]);
+        assert_eq!(frequencies(&["the", "on"], text), [2. / 6., 1. / 6.]);
+    }
+}
@@ -0,0 +1,451 @@
+use ndarray::{Array1, Array2, ArrayView1, ArrayView2, Axis};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::str::FromStr;
+
+// added to every variance so a constant column can't collapse a component
+const REG_COVAR: f64 = 1e-6;
+// training texts below this log-likelihood quantile count as out of distribution
+const OOD_QUANTILE: f64 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CovarianceType {
+    #[default]
+    Full,
+    /// Independent columns, one variance each
+    Diagonal,
+    /// One variance per component
+    Spherical,
+}
+
+impl FromStr for CovarianceType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(Self::Full),
+            "diagonal" => Ok(Self::Diagonal),
+            "spherical" => Ok(Self::Spherical),
+            _ => Err(format!(
+                "unknown covariance type `{s}`, expected full, diagonal or spherical"
+            )),
+        }
+    }
+}
+
+/// Gaussian mixture fitted with EM, gives real posteriors where KMeans only
+/// has distances.
+///
+/// linfa's mixture only does full covariances, so this one is fitted here.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GaussianMixture {
+    covariance: CovarianceType,
+    weights: Array1<f64>,
+    means: Array2<f64>,
+    // lower cholesky factor of each component covariance
+    cholesky: Vec<Array2<f64>>,
+    log_det: Array1<f64>,
+    /// Log-likelihood below which a text looks unlike anything trained on
+    pub ood_threshold: f64,
+}
+
+impl GaussianMixture {
+    /// EM starting from hard `labels`, usually a KMeans fit.
+    pub fn fit(
+        features: ArrayView2<f64>,
+        labels: &Array1<usize>,
+        covariance: CovarianceType,
+        max_iterations: usize,
+        tolerance: f64,
+    ) -> Self {
+        let n_components = labels.iter().max().map_or(1, |max| max + 1);
+
+        let mut responsibilities = Array2::zeros((features.nrows(), n_components));
+        for (i, &label) in labels.iter().enumerate() {
+            responsibilities[[i, label]] = 1.;
+        }
+
+        let mut gmm = Self::m_step(features, &responsibilities, covariance);
+        let mut previous = f64::NEG_INFINITY;
+
+        for _ in 0..max_iterations {
+            let (next, log_likelihood) = gmm.e_step(features);
+            responsibilities = next;
+            gmm = Self::m_step(features, &responsibilities, covariance);
+
+            if (log_likelihood - previous).abs() < tolerance {
+                break;
+            }
+            previous = log_likelihood;
+        }
+
+        let mut log_likelihoods: Vec<f64> = features
+            .axis_iter(Axis(0))
+            .map(|x| gmm.log_likelihood(x))
+            .collect();
+        log_likelihoods.sort_by(f64::total_cmp);
+
+        let cutoff = (OOD_QUANTILE * log_likelihoods.len() as f64) as usize;
+        gmm.ood_threshold = log_likelihoods
+            .get(cutoff)
+            .copied()
+            .unwrap_or(f64::NEG_INFINITY);
+
+        gmm
+    }
+
+    pub fn n_components(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn n_features(&self) -> usize {
+        self.means.ncols()
+    }
+
+    pub fn means(&self) -> ArrayView2<'_, f64> {
+        self.means.view()
+    }
+
+    pub fn covariance_type(&self) -> CovarianceType {
+        self.covariance
+    }
+
+    /// P(component | x)
+    pub fn posterior(&self, x: ArrayView1<f64>) -> Array1<f64> {
+        let log_joint = self.log_joint(x);
+        let log_evidence = log_sum_exp(&log_joint);
+
+        log_joint.mapv(|log_p| (log_p - log_evidence).exp())
+    }
+
+    /// ln p(x) under the whole mixture.
+    pub fn log_likelihood(&self, x: ArrayView1<f64>) -> f64 {
+        log_sum_exp(&self.log_joint(x))
+    }
+
+    pub fn predict(&self, features: ArrayView2<f64>) -> Array1<usize> {
+        features
+            .axis_iter(Axis(0))
+            .map(|x| {
+                let log_joint = self.log_joint(x);
+                (0..log_joint.len())
+                    .max_by(|&a, &b| log_joint[a].total_cmp(&log_joint[b]))
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+
+    /// ln P(component) + ln p(x | component)
+    fn log_joint(&self, x: ArrayView1<f64>) -> Array1<f64> {
+        let d = self.n_features() as f64;
+
+        (0..self.n_components())
+            .map(|c| {
+                let diff = &x - &self.means.row(c);
+                let z = forward_substitute(&self.cholesky[c], diff.view());
+                let mahalanobis = z.dot(&z);
+
+                self.weights[c].ln() - 0.5 * (d * (2. * PI).ln() + self.log_det[c] + mahalanobis)
+            })
+            .collect()
+    }
+
+    fn e_step(&self, features: ArrayView2<f64>) -> (Array2<f64>, f64) {
+        let mut responsibilities = Array2::zeros((features.nrows(), self.n_components()));
+        let mut total = 0.;
+
+        for (x, mut row) in features
+            .axis_iter(Axis(0))
+            .zip(responsibilities.axis_iter_mut(Axis(0)))
+        {
+            let log_joint = self.log_joint(x);
+            let log_evidence = log_sum_exp(&log_joint);
+
+            row.assign(&log_joint.mapv(|log_p| (log_p - log_evidence).exp()));
+            total += log_evidence;
+        }
+
+        (responsibilities, total / features.nrows().max(1) as f64)
+    }
+
+    fn m_step(
+        features: ArrayView2<f64>,
+        responsibilities: &Array2<f64>,
+        covariance: CovarianceType,
+    ) -> Self {
+        let (n, d) = features.dim();
+        let counts = responsibilities.sum_axis(Axis(0)) + 10. * f64::EPSILON;
+        let means = responsibilities.t().dot(&features) / counts.view().insert_axis(Axis(1));
+
+        let mut cholesky = Vec::with_capacity(counts.len());
+        let mut log_det = Array1::zeros(counts.len());
+
+        for (c, &count) in counts.iter().enumerate() {
+            let resp = responsibilities.column(c);
+            let centered = &features - &means.row(c);
+            let weighted = &centered * &resp.insert_axis(Axis(1));
+
+            let mut sigma = match covariance {
+                CovarianceType::Full => weighted.t().dot(&centered) / count,
+                CovarianceType::Diagonal => {
+                    let variances = (&weighted * &centered).sum_axis(Axis(0)) / count;
+                    Array2::from_diag(&variances)
+                }
+                CovarianceType::Spherical => {
+                    let variance = (&weighted * &centered).sum() / (count * d.max(1) as f64);
+                    Array2::eye(d) * variance
+                }
+            };
+            sigma.diag_mut().mapv_inplace(|x| x + REG_COVAR);
+
+            let factor = cholesky_decompose(&sigma);
+            log_det[c] = 2. * factor.diag().mapv(f64::ln).sum();
+            cholesky.push(factor);
+        }
+
+        Self {
+            covariance,
+            weights: counts / n.max(1) as f64,
+            means,
+            cholesky,
+            log_det,
+            ood_threshold: f64::NEG_INFINITY,
+        }
+    }
+}
+
+/// Lower triangular L with L Lᵀ = matrix, the matrix is regularized so it is
+/// always positive definite.
+fn cholesky_decompose(matrix: &Array2<f64>) -> Array2<f64> {
+    let d = matrix.nrows();
+    let mut lower = Array2::<f64>::zeros((d, d));
+
+    for i in 0..d {
+        for j in 0..=i {
+            let sum: f64 = (0..j).map(|k| lower[[i, k]] * lower[[j, k]]).sum();
+
+            if i == j {
+                lower[[i, j]] = (matrix[[i, i]] - sum).max(REG_COVAR).sqrt();
+            } else {
+                lower[[i, j]] = (matrix[[i, j]] - sum) / lower[[j, j]];
+            }
+        }
+    }
+
+    lower
+}
+
+/// Solves L z = b.
+fn forward_substitute(lower: &Array2<f64>, b: ArrayView1<f64>) -> Array1<f64> {
+    let mut z = Array1::<f64>::zeros(b.len());
+
+    for i in 0..b.len() {
+        let sum: f64 = (0..i).map(|k| lower[[i, k]] * z[k]).sum();
+        z[i] = (b[i] - sum) / lower[[i, i]];
+    }
+
+    z
+}
+
+fn log_sum_exp(values: &Array1<f64>) -> f64 {
+    let max = values.fold(f64::NEG_INFINITY, |max, &x| max.max(x));
+
+    if max.is_finite() {
+        max + values.mapv(|x| (x - max).exp()).sum().ln()
+    } else {
+        max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{Array, array};
+
+    /// Deterministic standard normals, xorshift and Box-Muller.
+    fn normals(mut state: u64) -> impl Iterator<Item = f64> {
+        let mut uniform = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+
+        std::iter::from_fn(move || {
+            let (u, v) = (uniform().max(f64::MIN_POSITIVE), uniform());
+            Some((-2. * u.ln()).sqrt() * (2. * PI * v).cos())
+        })
+    }
+
+    /// 300 points around (0, 0) with sd 1 and 700 around (8, 4) with sd
+    /// (2, 0.5), and labels split at x = 4.
+    fn two_blobs() -> (Array2<f64>, Array1<usize>) {
+        let mut noise = normals(42);
+        let mut features = Array2::zeros((1000, 2));
+
+        for (i, mut row) in features.axis_iter_mut(Axis(0)).enumerate() {
+            let (a, b) = (noise.next().unwrap(), noise.next().unwrap());
+            row.assign(&if i < 300 {
+                array![a, b]
+            } else {
+                array![8. + 2. * a, 4. + 0.5 * b]
+            });
+        }
+
+        let labels = features.column(0).mapv(|x| usize::from(x > 4.));
+
+        (features, labels)
+    }
+
+    fn covariance(gmm: &GaussianMixture, c: usize) -> Array2<f64> {
+        gmm.cholesky[c].dot(&gmm.cholesky[c].t())
+    }
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{actual} isn't within {tolerance} of {expected}"
+        );
+    }
+
+    #[test]
+    fn recovers_two_components() {
+        let (features, labels) = two_blobs();
+
+        for covariance_type in [CovarianceType::Full, CovarianceType::Diagonal] {
+            let gmm = GaussianMixture::fit(features.view(), &labels, covariance_type, 200, 1e-9);
+
+            assert_close(gmm.weights[0], 0.3, 0.02);
+            assert_close(gmm.weights[1], 0.7, 0.02);
+
+            for (actual, expected) in gmm.means.iter().zip([0., 0., 8., 4.]) {
+                assert_close(*actual, expected, 0.2);
+            }
+
+            let (first, second) = (covariance(&gmm, 0), covariance(&gmm, 1));
+            assert_close(first[[0, 0]], 1., 0.2);
+            assert_close(first[[1, 1]], 1., 0.2);
+            assert_close(second[[0, 0]], 4., 0.5);
+            assert_close(second[[1, 1]], 0.25, 0.05);
+            assert_close(second[[0, 1]], 0., 0.1);
+        }
+    }
+
+    #[test]
+    fn spherical_shares_one_variance() {
+        let (features, labels) = two_blobs();
+        let gmm = GaussianMixture::fit(
+            features.view(),
+            &labels,
+            CovarianceType::Spherical,
+            200,
+            1e-9,
+        );
+        let second = covariance(&gmm, 1);
+
+        assert_close(second[[0, 0]], second[[1, 1]], 1e-9);
+        // the mean of 4 and 0.25
+        assert_close(second[[0, 0]], 2.125, 0.3);
+    }
+
+    #[test]
+    fn log_likelihood_never_drops() {
+        let (features, _) = two_blobs();
+        // a poor start, every other point
+        let labels = Array::from_iter((0..features.nrows()).map(|i| i % 2));
+
+        let mut previous = f64::NEG_INFINITY;
+        for iterations in 0..15 {
+            // a tolerance of 0 never stops early
+            let gmm = GaussianMixture::fit(
+                features.view(),
+                &labels,
+                CovarianceType::Full,
+                iterations,
+                0.,
+            );
+            let (_, log_likelihood) = gmm.e_step(features.view());
+
+            assert!(
+                log_likelihood >= previous - 1e-9,
+                "{log_likelihood} < {previous} after {iterations} iterations"
+            );
+            previous = log_likelihood;
+        }
+    }
+
+    #[test]
+    fn constant_columns_stay_finite() {
+        let (features, labels) = two_blobs();
+        let mut with_constant = Array2::from_elem((features.nrows(), 3), 5.);
+        with_constant
+            .slice_mut(ndarray::s![.., ..2])
+            .assign(&features);
+
+        for covariance_type in [
+            CovarianceType::Full,
+            CovarianceType::Diagonal,
+            CovarianceType::Spherical,
+        ] {
+            let gmm =
+                GaussianMixture::fit(with_constant.view(), &labels, covariance_type, 50, 1e-9);
+
+            for x in with_constant.axis_iter(Axis(0)).step_by(97) {
+                assert!(gmm.log_likelihood(x).is_finite());
+                assert_close(gmm.posterior(x).sum(), 1., 1e-9);
+            }
+            assert!(gmm.ood_threshold.is_finite());
+        }
+
+        // a component that's a single repeated point
+        let same = Array2::from_elem((10, 2), 1.);
+        let gmm = GaussianMixture::fit(
+            same.view(),
+            &Array1::zeros(10),
+            CovarianceType::Full,
+            10,
+            1e-9,
+        );
+        assert!(gmm.log_likelihood(same.row(0)).is_finite());
+    }
+
+    #[test]
+    fn agrees_with_linfa() {
+        use linfa::DatasetBase;
+        use linfa::traits::Fit;
+        use linfa_clustering::GaussianMixtureModel;
+
+        let (features, labels) = two_blobs();
+        let ours = GaussianMixture::fit(features.view(), &labels, CovarianceType::Full, 500, 1e-10);
+
+        let theirs = GaussianMixtureModel::params(2)
+            .tolerance(1e-10)
+            .max_n_iterations(500)
+            .reg_covariance(REG_COVAR)
+            .fit(&DatasetBase::from(features.clone()))
+            .unwrap();
+
+        // linfa numbers its components in whatever order KMeans found them
+        let order: Vec<usize> = if theirs.means()[[0, 0]] < theirs.means()[[1, 0]] {
+            vec![0, 1]
+        } else {
+            vec![1, 0]
+        };
+
+        for (c, &theirs_c) in order.iter().enumerate() {
+            assert_close(ours.weights[c], theirs.weights()[theirs_c], 1e-3);
+
+            for j in 0..2 {
+                assert_close(ours.means[[c, j]], theirs.means()[[theirs_c, j]], 1e-2);
+
+                for k in 0..2 {
+                    assert_close(
+                        covariance(&ours, c)[[j, k]],
+                        theirs.covariances()[[theirs_c, j, k]],
+                        1e-2,
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+const MTLD_THRESHOLD: f64 = 0.72;
+const HDD_SAMPLE: usize = 42;
+const LONG_WORD: usize = 7;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LexicalMetrics {
+    pub type_token_ratio: f64,
+    pub mtld: f64, // measure of textual lexical diversity, length robust
+    pub hdd: f64,  // hypergeometric distribution diversity, length robust
+    pub avg_word_length: f64,
+    pub long_word_ratio: f64, // words of 7+ chars / words
+    pub flesch_reading_ease: f64,
+    pub flesch_kincaid_grade: f64,
+}
+
+impl LexicalMetrics {
+    pub(crate) fn new(words: &[&str], sentence_count: usize) -> Self {
+        if words.is_empty() {
+            return Self {
+                type_token_ratio: 0.,
+                mtld: 0.,
+                hdd: 0.,
+                avg_word_length: 0.,
+                long_word_ratio: 0.,
+                flesch_reading_ease: 0.,
+                flesch_kincaid_grade: 0.,
+            };
+        }
+
+        let n = words.len() as f64;
+        let types = words.iter().collect::<HashSet<_>>().len() as f64;
+
+        let lengths = words.iter().map(|word| word.chars().count());
+        let letters = lengths.clone().sum::<usize>() as f64;
+        let long = lengths.filter(|&len| len >= LONG_WORD).count() as f64;
+
+        let syllables = words.iter().map(|word| syllables(word)).sum::<usize>() as f64;
+        let words_per_sentence = n / sentence_count.max(1) as f64;
+        let syllables_per_word = syllables / n;
+
+        Self {
+            type_token_ratio: types / n,
+            mtld: (mtld(words.iter()) + mtld(words.iter().rev())) / 2.,
+            hdd: hdd(words),
+            avg_word_length: letters / n,
+            long_word_ratio: long / n,
+            flesch_reading_ease: 206.835 - 1.015 * words_per_sentence - 84.6 * syllables_per_word,
+            flesch_kincaid_grade: 0.39 * words_per_sentence + 11.8 * syllables_per_word - 15.59,
+        }
+    }
+}
+
+/// One directional pass, counts how many times the running TTR drops to the
+/// threshold and reports the mean segment length.
+fn mtld<'a>(words: impl ExactSizeIterator<Item = &'a &'a str>) -> f64 {
+    let n = words.len() as f64;
+
+    let mut factors = 0.;
+    let mut types = HashSet::new();
+    let mut tokens = 0usize;
+
+    for word in words {
+        tokens += 1;
+        types.insert(word);
+
+        if (types.len() as f64 / tokens as f64) <= MTLD_THRESHOLD {
+            factors += 1.;
+            types.clear();
+            tokens = 0;
+        }
+    }
+
+    if tokens > 0 {
+        let ttr = types.len() as f64 / tokens as f64;
+        factors += (1. - ttr) / (1. - MTLD_THRESHOLD);
+    }
+
+    if factors > 0. { n / factors } else { n }
+}
+
+/// Expected TTR of a random 42 word sample, short texts use every word.
+fn hdd(words: &[&str]) -> f64 {
+    let n = words.len();
+    let sample = HDD_SAMPLE.min(n);
+
+    let mut frequencies: HashMap<&str, usize> = HashMap::new();
+    for word in words {
+        *frequencies.entry(word).or_default() += 1;
+    }
+
+    frequencies
+        .values()
+        .map(|&freq| {
+            // chance the word is absent from the sample, drawn without replacement
+            let absent = (0..sample)
+                .map(|i| (n - freq).saturating_sub(i) as f64 / (n - i) as f64)
+                .product::<f64>();
+
+            (1. - absent) / sample as f64
+        })
+        .sum()
+}
+
+/// Vowel group heuristic, good enough for readability formulas.
+fn syllables(word: &str) -> usize {
+    let mut count = 0;
+    let mut previous_vowel = false;
+
+    for c in word.chars() {
+        let vowel = matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+        if vowel && !previous_vowel {
+            count += 1;
+        }
+        previous_vowel = vowel;
+    }
+
+    if count > 1 && word.ends_with('e') && !word.ends_with("le") {
+        count -= 1;
+    }
+
+    count.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &str) -> Vec<&str> {
+        text.split_whitespace().collect()
+    }
+
+    fn mtld_both_ways(text: &str) -> f64 {
+        let words = words(text);
+        (mtld(words.iter()) + mtld(words.iter().rev())) / 2.
+    }
+
+    #[test]
+    fn mtld_worked_examples() {
+        // worked by hand from McCarthy & Jarvis (2010): the second "people"
+        // takes the TTR to 4/6, one full factor each way and a remainder at
+        // TTR 1
+        assert_eq!(
+            mtld_both_ways("of the people by the people for the people"),
+            9.
+        );
+
+        // every second token closes a factor
+        assert_eq!(mtld_both_ways("a a b b"), 2.);
+
+        // forward one factor and a 1.0 remainder, backward no full factor and
+        // a 3/4 remainder worth (1 - 0.75) / (1 - 0.72)
+        let backward = 4. / ((1. - 0.75) / (1. - MTLD_THRESHOLD));
+        assert!((mtld_both_ways("a b a c") - (4. + backward) / 2.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn hdd_matches_the_hypergeometric() {
+        // 48 tokens, 21 types, sum over types of (1 - C(48 - f, 42) / C(48, 42)) / 42
+        let text = "the cat and the dog and the bird saw a cat on the mat and a dog on \
+                    the log then the bird flew over the cat and the dog ran to the log by \
+                    the mat near a tree where a bird sat and the cat slept";
+
+        assert!((hdd(&words(text)) - 0.4632770482677976).abs() < 1e-12);
+    }
+
+    #[test]
+    fn short_texts_use_every_word() {
+        // all distinct, every type is certainly drawn
+        assert!((hdd(&words("one two three four")) - 1.).abs() < 1e-12);
+        assert!((hdd(&words("one one one one")) - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn empty_text() {
+        let metrics = LexicalMetrics::new(&[], 0);
+        assert_eq!(metrics.mtld, 0.);
+        assert_eq!(metrics.hdd, 0.);
+    }
+}
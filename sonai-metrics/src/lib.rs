@@ -1,69 +1,161 @@
 #![deny(clippy::all)]
 
-use aho_corasick::AhoCorasick;
-use linfa_clustering::KMeans;
 use linfa_nn::distance::Distance;
-use linfa_nn::distance::LInfDist;
-use ndarray::{Array1, Array2, ArrayView1, Axis};
-use pulldown_cmark::Event;
-use pulldown_cmark::Parser;
-use pulldown_cmark::Tag;
-use serde::Serialize;
+use ndarray::{Array1, Array2, ArrayView1, ArrayView2, Axis, concatenate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
 use unicode_segmentation::UnicodeSegmentation;
 
-pub const DIST_FN: LInfDist = LInfDist;
-pub type DistanceFunction = LInfDist;
+mod builtin;
+mod distance;
+#[cfg(feature = "embeddings")]
+mod embedding;
+mod function_words;
+mod gmm;
+mod lexical;
+mod metric;
+mod model;
+mod ngram;
+mod normalize;
+mod pca;
+mod punctuation;
+mod scaler;
+
+pub use distance::{DistanceFunction, DistanceKind};
+#[cfg(feature = "embeddings")]
+pub use embedding::Embedder;
+pub use function_words::FUNCTION_WORDS;
+pub use gmm::{CovarianceType, GaussianMixture};
+pub use lexical::LexicalMetrics;
+pub use metric::{Document, Metric, MetricRegistry, MetricValue, MetricValues};
+pub use model::{Clusterer, Label, Model, nearest_centroid};
+pub use ngram::{NgramModel, NgramUnit, Perplexity};
+pub use punctuation::PunctuationProfile;
+
+pub use normalize::{canonical_quote, is_invisible, normalize};
+pub use pca::Pca;
+use punctuation::oxford_commas;
+pub use scaler::{Scaler, ScalerKind};
+
+/// What models were trained with before the distance was configurable.
+pub const DIST_FN: DistanceFunction = DistanceFunction::LInf;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Sources holding the built-in word lists and character tables, training
+/// hashes them so a lexicon edit shows up in the run manifest.
+pub const LEXICON_SOURCES: &[(&str, &str)] = &[
+    ("builtin.rs", include_str!("builtin.rs")),
+    ("function_words.rs", include_str!("function_words.rs")),
+    ("normalize.rs", include_str!("normalize.rs")),
+];
+
+/// Optional feature blocks appended after the registry columns of
+/// [`features_from_metrics`]. The default set is the legacy layout.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureSet {
+    pub rhythm: bool,
+    pub lexical: bool,
+    /// Relative frequency of each word, in order. Usually [`FUNCTION_WORDS`].
+    pub function_words: Option<Vec<String>>,
+    pub punctuation: bool,
+    /// Needs a language model, see [`TextMetricFactory::with_language_model`].
+    pub perplexity: bool,
+    /// PCA reduced sentence embedding width, 0 = off. Computing it needs the
+    /// `embeddings` cargo feature and [`TextMetricFactory::with_embedder`].
+    pub embeddings: usize,
+}
+
+impl FeatureSet {
+    /// Columns of the enabled blocks, see [`TextMetricFactory::n_features`]
+    /// for the whole matrix.
+    pub fn n_block_features(&self) -> usize {
+        (if self.rhythm { 6 } else { 0 })
+            + if self.lexical { 7 } else { 0 }
+            + self.function_words.as_ref().map_or(0, Vec::len)
+            + if self.punctuation { 9 } else { 0 }
+            + if self.perplexity { 2 } else { 0 }
+            + self.embeddings
+    }
+
+    /// Where the columns of `subset` sit in a matrix laid out for `self`, with
+    /// `n_metrics` registry columns in front. `subset` can only turn blocks off.
+    pub fn columns_of(&self, subset: &FeatureSet, n_metrics: usize) -> Vec<usize> {
+        let blocks = [
+            (if self.rhythm { 6 } else { 0 }, subset.rhythm),
+            (if self.lexical { 7 } else { 0 }, subset.lexical),
+            (
+                self.function_words.as_ref().map_or(0, Vec::len),
+                subset.function_words.is_some(),
+            ),
+            (if self.punctuation { 9 } else { 0 }, subset.punctuation),
+            (if self.perplexity { 2 } else { 0 }, subset.perplexity),
+            (self.embeddings, subset.embeddings > 0),
+        ];
+
+        let mut columns: Vec<usize> = (0..n_metrics).collect();
+        let mut start = n_metrics;
+
+        for (width, keep) in blocks {
+            if keep {
+                columns.extend(start..start + width);
+            }
+            start += width;
+        }
+
+        columns
+    }
+}
 
 #[derive(Debug, Serialize)]
 pub struct TextMetrics {
-    // higher = more AI-like
-    pub emoji_rate: f64,    // Emoji * 2 / sentences
-    pub buzzword_rate: f64, // Buzzwords
-    //
-    pub not_just_count: f64,              // It's not just _, it's _
-    pub html_escape_count: f64,           // &amp;
-    pub devlog_count: f64,                // Devlog #whatever
-    pub backstory_count: f64,             // I built this for the people of America.
-    pub incorrect_perspective_count: f64, // We, they, you, etc
-    pub mr_fancy_pants: f64,              //I am quite profficient in English grammar!
-
-    pub irregular_ellipsis: f64,   // bad ellipses
-    pub irregular_quotations: f64, // Fancy quotation marks / total quotation marks
-    pub irregular_dashes: f64,     // Em-dashes / total dashes
-    pub irregular_markdown: f64,   // bad markdown syntax present
-    pub irregular_arrows: f64,     // -> but the non hyphen-minus greater than version
-
-    //pub i_speak_of_the_english: f64, // Bad english
-    pub labels: f64,
-    pub hashtags: f64,
+    // higher = more AI-like, one per registered metric
+    #[serde(flatten)]
+    pub values: MetricValues,
+
+    // lower = more AI-like, generated text has a very even rhythm
+    pub sentence_length_mean: f64, // words per sentence
+    pub sentence_length_variance: f64,
+    pub sentence_length_cv: f64, // coefficient of variation, std / mean
+    pub paragraph_length_mean: f64, // words per paragraph
+    pub paragraph_length_variance: f64,
+    pub paragraph_length_cv: f64,
+
+    pub lexical: Option<LexicalMetrics>, // only with FeatureSet::lexical
+    pub function_words: Option<Vec<f64>>, // only with FeatureSet::function_words
+    pub punctuation: Option<PunctuationProfile>, // only with FeatureSet::punctuation
+    pub perplexity: Option<Perplexity>,  // only with FeatureSet::perplexity
+    pub embedding: Option<Vec<f64>>,     // only with FeatureSet::embeddings
 }
 
 impl fmt::Display for TextMetrics {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         const COLUMNS: u8 = 2u8;
 
-        let metrics = &[
-            ("emoji", self.emoji_rate),
-            ("not_just", self.not_just_count),
-            ("buzzword", self.buzzword_rate),
-            ("html", self.html_escape_count),
-            ("irr_ell", self.irregular_ellipsis),
-            ("irr_quote", self.irregular_quotations),
-            ("irr_dash", self.irregular_dashes),
-            ("irr_arr", self.irregular_arrows),
-            ("irr_md", self.irregular_markdown),
-            ("fancy", self.mr_fancy_pants),
-            ("bad_per", self.incorrect_perspective_count),
-            ("devlog", self.devlog_count),
-            ("labels", self.labels),
-            ("hashtags", self.hashtags),
-            ("backstory", self.backstory_count),
-        ];
+        let mut metrics: Vec<_> = self
+            .values
+            .iter()
+            .map(|metric| (metric.name, metric.value))
+            .collect();
+
+        metrics.extend([
+            ("sent_cv", self.sentence_length_cv),
+            ("para_cv", self.paragraph_length_cv),
+        ]);
+
+        if let Some(lexical) = &self.lexical {
+            metrics.extend([
+                ("mtld", lexical.mtld),
+                ("long_word", lexical.long_word_ratio),
+                ("flesch", lexical.flesch_reading_ease),
+                ("fk_grade", lexical.flesch_kincaid_grade),
+            ]);
+        }
 
         let mut cell = 0u8;
 
-        for &(metric, value) in metrics {
+        for &(metric, value) in &metrics {
             if value == 0. {
                 continue;
             }
@@ -104,227 +196,86 @@ impl fmt::Display for TextMetrics {
 
 #[derive(Debug)]
 pub struct TextMetricFactory {
-    buzzword_ahocorasick: AhoCorasick,
-    negative_buzzword_ahocorasick: AhoCorasick,
-    not_just_ahocorasick: AhoCorasick,
-    devlog_ahocorasick: AhoCorasick,
-    irr_ell_ahocorasick: AhoCorasick,
-    backstory_ahocorasick: AhoCorasick,
-    incorrect_perspective_ahocorasick: AhoCorasick,
-    mr_fancy_pants_ahocorasick: AhoCorasick,
+    registry: MetricRegistry,
+
+    features: FeatureSet,
+    function_word_index: HashMap<String, usize>,
+    language_model: Option<NgramModel>,
+    #[cfg(feature = "embeddings")]
+    embedder: Option<(Embedder, Pca)>,
 }
 
 impl TextMetricFactory {
     pub fn new() -> Result<Self, aho_corasick::BuildError> {
         Ok(Self {
-            buzzword_ahocorasick: AhoCorasick::new([
-                "the app",
-                "-powered",
-                "-melting",
-                "powered by",
-                "based on",
-                "-like",
-                "todo app",
-                "interactive cards",
-                "modern",
-                "delivers",
-                "delivers both",
-                "across all devices",
-                "style and usability",
-                "real-time",
-                "calm, reflective space",
-                "simulate",
-                "self-care",
-                "meaningful",
-                "user interaction",
-                "digital wellness",
-                "user-friendly interface",
-                "responsive",
-                "auto-typing",
-                "engagement",
-                "community",
-                "ambitious goal",
-                "world of data",
-                "programming toolkit",
-                "summer of learning",
-                "and a custom",
-                "foundational principles",
-                "began to wonder",
-                "i'm announcing",
-                "i’m announcing",
-                "fully featured",
-                "next.js 13",
-                "next.js 14",
-                "next.js 13/14",
-                "svelte 4",
-                "app router",
-                "modern",
-                "web dashboard",
-                "step-by-step",
-                "excited",
-                "build this",
-                "inner workings",
-                "live code editor",
-                "new project",
-                "kicking off",
-                "lightweight",
-                "in the browser",
-                "brutalism",
-                "morphism",
-                "comprehensive",
-                "philosophy",
-                "revolutionary",
-                "wisdom",
-                "leetcode",
-                "global accessibility",
-                "developers",
-                "harmony of tradition and innovation",
-                "intuitive",
-                "powerful features",
-                "cross-platform",
-                "inspiration",
-                "technical architecture",
-                "users can",
-                "rewarding feel",
-                "progress tracking",
-                "understandable",
-                "digital co-pilot",
-                "significantly improves usability",
-                "easier to navigate",
-                "react for the frontend",
-                "stylish",
-                "mobile-",
-                "ui/ux",
-                "the single solution",
-                "fully customizable",
-                "about to change everything",
-                "solved that problem",
-                "the same tech behind",
-                "lives in its own",
-                "is like a",
-                "kubernetes",
-                "orchestrated",
-                "microservices architecture",
-                "corporate jargon",
-                "✨", // This emoji sucks
-                "buttery-smooth",
-                "biggest competitor",
-                "it lets you",
-            ])?,
-            negative_buzzword_ahocorasick: AhoCorasick::new(["modern english", "made the app"])?,
-            mr_fancy_pants_ahocorasick: AhoCorasick::new(["(e.g.", "(formerly"])?,
-            not_just_ahocorasick: AhoCorasick::new([
-                "more than just",
-                "isn’t a",
-                "isn't a",
-                "this isn’t a prototype",
-                "isn’t just a",
-                "isn't just a",
-                "it’s not just",
-                "it's not just",
-                "i'm not just",
-                "i’m not just",
-                "it’s just not",
-                "it's just not",
-                "i'm just not",
-                "i’m just not",
-                "isn’t just",
-                "isn't just",
-                "didn't just",
-                "didn’t just",
-                "more than a",
-                "it’s more",
-                "it's more",
-            ])?,
-            devlog_ahocorasick: AhoCorasick::new([
-                "dev log",
-                "dev-log",
-                "day",
-                "devlog #",
-                "dev log #",
-                "dev-log #",
-                "day #",
-                "first devlog",
-                "today,",
-                "june ",
-                "july ",
-                "august ",
-                "jun ",
-                "jul ",
-                "aug ",
-                "-06-",
-                "-07-",
-                "-08-",
-                "/06/",
-                "/07/",
-                "/08/",
-                ".06.",
-                ".07.",
-                ".08.",
-                "/6/",
-                "/7/",
-                "/8/",
-                "this week was all about",
-                "the project",
-                "what’s next",
-                "what's next",
-                "next steps",
-                "why it matters",
-                "more coming soon",
-                "what i built",
-            ])?,
-            irr_ell_ahocorasick: AhoCorasick::new(["…", "..."])?,
-            incorrect_perspective_ahocorasick: AhoCorasick::new([
-                " we're ",
-                " we ",
-                " they're ",
-                " us ",
-                " our ",
-                " ours ",
-                " ourselves ",
-                " them ",
-                " people ",
-                " theirs ",
-                " themselves ",
-                " oneself ",
-            ])?,
-            backstory_ahocorasick: AhoCorasick::new([
-                "as a",
-                "high school student",
-                "middle school student",
-                "preparing for",
-                "exams",
-                "was born from",
-                "personal frustration",
-                "makes it unique",
-                "and eventually",
-                "the intention",
-                "it’s been a journey",
-                "it's been a journey",
-                "a journey",
-                "it’s all about",
-                "it's all about",
-                "leverage that knowledge",
-                "dive into",
-                "become a versatile programmer",
-                "my adventure",
-                "foundational principles",
-                "how computers truly work",
-                "the world of data",
-                "an ambitious goal",
-                "excited to build",
-                "programming toolkit",
-                "summer of learning",
-                "something insane",
-                "think of it like",
-                "drowning in",
-                "last week",
-                "next week",
-            ])?,
+            registry: MetricRegistry::builtin()?,
+            features: FeatureSet::default(),
+            function_word_index: HashMap::new(),
+            language_model: None,
+            #[cfg(feature = "embeddings")]
+            embedder: None,
         })
     }
 
-    pub fn calculate_iter<I, S>(&self, texts: I) -> impl Iterator<Item = TextMetrics>
+    /// Registers a metric after the built-in ones, see [`Metric`].
+    pub fn with_metric(mut self, metric: impl Metric + 'static) -> Self {
+        self.registry.register(metric);
+        self
+    }
+
+    pub fn registry(&self) -> &MetricRegistry {
+        &self.registry
+    }
+
+    /// Width of the matrix [`features_from_metrics_with`] builds from these
+    /// metrics.
+    pub fn n_features(&self) -> usize {
+        self.registry.len() + self.features.n_block_features()
+    }
+
+    /// [`Metric::scale`] of every column, blocks are weighted 1.
+    pub fn weights(&self) -> Vec<f64> {
+        self.registry
+            .scales()
+            .chain(std::iter::repeat_n(1., self.features.n_block_features()))
+            .collect()
+    }
+
+    /// Embedder and fitted projection scoring [`FeatureSet::embeddings`].
+    #[cfg(feature = "embeddings")]
+    pub fn with_embedder(mut self, embedder: Embedder, pca: Pca) -> Self {
+        self.embedder = Some((embedder, pca));
+        self
+    }
+
+    /// Language model scoring [`FeatureSet::perplexity`].
+    pub fn with_language_model(mut self, model: NgramModel) -> Self {
+        self.language_model = Some(model);
+        self
+    }
+
+    /// Also compute the optional metrics `features` needs.
+    pub fn with_features(mut self, features: FeatureSet) -> Self {
+        self.function_word_index = features
+            .function_words
+            .iter()
+            .flatten()
+            .enumerate()
+            .map(|(i, word)| (normalize(word), i))
+            .collect();
+
+        self.features = features;
+        self
+    }
+
+    pub fn features(&self) -> &FeatureSet {
+        &self.features
+    }
+
+    pub fn calculate_iter<I, S>(
+        &self,
+        texts: I,
+    ) -> impl Iterator<Item = Result<TextMetrics, String>>
     where
         I: IntoIterator<Item = S>,
         S: AsRef<str>,
@@ -332,184 +283,299 @@ impl TextMetricFactory {
         texts.into_iter().map(|s| self.calculate(s.as_ref()))
     }
 
-    pub fn calculate(&self, text: &str) -> TextMetrics {
-        // existing markdown vs non-markdown
-        let markdown = Parser::new(text)
-            .filter(|event| {
-                matches!(
-                    event,
-                    Event::InlineMath(_)
-                        | Event::DisplayMath(_)
-                        | Event::Html(_)
-                        | Event::FootnoteReference(_)
-                        | Event::TaskListMarker(_)
-                        | Event::Rule
-                        | Event::InlineHtml(_)
-                        | Event::Start(
-                            Tag::BlockQuote(_)
-                                | Tag::CodeBlock(_)
-                                | Tag::FootnoteDefinition(_)
-                                | Tag::Emphasis
-                                | Tag::Subscript
-                                | Tag::Superscript
-                                | Tag::Strong
-                                | Tag::Strikethrough
-                                | Tag::Heading { .. }
-                                | Tag::Link { .. }
-                                | Tag::Image { .. }
-                        )
-                )
-            })
-            .count()
-            + text.matches('•').count(); // Lists are OK, this shit is not
+    /// Fails only when the embedding model can't embed `text`.
+    pub fn calculate(&self, text: &str) -> Result<TextMetrics, String> {
+        // typography is judged on what was typed, lexicons on what was meant
+        let doc = Document::new(text);
+        let values = self.registry.compute(&doc);
 
-        let text = text.to_ascii_lowercase().trim().replace("\n\n", "\n");
+        let sentence_count = doc.sentence_count();
+        let words = doc.words();
 
-        let sentence_count = text
-            .split(['.', '!', '?', '\n'])
-            .filter(|s| !s.trim().is_empty())
-            .count()
-            .max(1);
+        let lexical = self
+            .features
+            .lexical
+            .then(|| LexicalMetrics::new(&words, sentence_count));
 
-        let mut labels = 0usize;
+        let function_words = self.features.function_words.as_ref().map(|list| {
+            let mut frequencies = vec![0.; list.len()];
 
-        for line in text.lines() {
-            if let Some((label, _)) = line.split_once(':') {
-                let label = label.trim();
-
-                if !label.is_empty()
-                    && label
-                        .chars()
-                        .all(|c| c.is_alphabetic() || c.is_whitespace())
-                {
-                    labels += 1;
+            for word in &words {
+                if let Some(&i) = self.function_word_index.get(*word) {
+                    frequencies[i] += 1.;
                 }
             }
-        }
 
-        let text = text.replace("\n", " ").replace("  ", " ");
+            let total = words.len().max(1) as f64;
+            frequencies.iter_mut().for_each(|freq| *freq /= total);
+
+            frequencies
+        });
+        let (sentence_mean, sentence_variance, sentence_cv) = dispersion(&doc.sentence_lengths);
+        let (paragraph_mean, paragraph_variance, paragraph_cv) = dispersion(&doc.paragraph_lengths);
+
+        let punctuation = self.features.punctuation.then(|| {
+            let oxford = doc.flat.unicode_sentences().map(oxford_commas).sum();
+            doc.punctuation.profile(oxford)
+        });
+
+        let perplexity = self
+            .features
+            .perplexity
+            .then(|| {
+                self.language_model
+                    .as_ref()
+                    .map(|lm| lm.perplexity(doc.raw))
+            })
+            .flatten();
 
-        let words = text.split_whitespace().filter(|w| !w.trim().is_empty());
+        #[cfg(feature = "embeddings")]
+        let embedding = match &self.embedder {
+            Some((embedder, pca)) if self.features.embeddings > 0 => {
+                let embedding = embedder
+                    .embed(&[doc.raw])
+                    .map_err(|err| format!("couldn't embed the text: {err}"))?;
 
-        let mut hashtags = 0usize;
-        for word in words {
-            if word.starts_with('#') && word.len() > 1 {
-                hashtags += 1;
+                Some(pca.transform(embedding.view()).row(0).to_vec())
             }
-        }
+            _ => None,
+        };
+
+        #[cfg(not(feature = "embeddings"))]
+        let embedding = None;
+
+        Ok(TextMetrics {
+            values,
+
+            sentence_length_mean: sentence_mean,
+            sentence_length_variance: sentence_variance,
+            sentence_length_cv: sentence_cv,
+            paragraph_length_mean: paragraph_mean,
+            paragraph_length_variance: paragraph_variance,
+            paragraph_length_cv: paragraph_cv,
+
+            lexical,
+            function_words,
+            punctuation,
+            perplexity,
+            embedding,
+        })
+    }
+}
 
-        let mut emoji_count = 0;
-        let mut irr_dash = 0;
-        let mut irr_quote = 0;
-        let mut irr_arr = 0;
+/// One column per registered metric, in registration order. Unweighted, see
+/// [`Scaler`].
+pub fn features_from_metrics(data: &[&TextMetrics]) -> Array2<f64> {
+    let n_features = data.first().map_or(0, |sample| sample.values.len());
+    let n_samples = data.len();
 
-        for grapheme in text.graphemes(true) {
-            if emojis::get(grapheme).is_some() {
-                emoji_count += 1;
-                continue;
-            }
+    let mut array = Array2::<f64>::zeros((n_samples, n_features));
 
-            let mut iter = grapheme.chars().peekable();
-
-            while let Some(c) = iter.next() {
-                match c {
-                    '–' | '—' | '‒' | '―' | '⸻' | '⸺' | '−' | '﹘' | '－' | '‑' | '‐' | '᠆'
-                    | '־' | '֊' => irr_dash += 1,
-                    '→' | '↑' | '↓' | '↔' | '↕' | '⇒' | '⇐' | '⇑' | '⇓' | '➔' | '➜' => {
-                        irr_arr += 1
-                    }
-                    '“' | '”' | '‘' | '’' => irr_quote += 1,
-                    '-' => {
-                        if iter.peek().is_some_and(|x| !x.is_whitespace()) {
-                            irr_dash += 1;
-                        }
-                    }
-                    _ => {}
-                }
-            }
+    for (i, sample) in data.iter().enumerate() {
+        for (j, metric) in sample.values.iter().take(n_features).enumerate() {
+            array[[i, j]] = metric.value;
         }
+    }
 
-        // tradeoff is fine for a match list this small
-        let irr_ell = self.irr_ell_ahocorasick.find_iter(&text).count();
+    array
+}
 
-        let sc = sentence_count as f64;
+/// [`features_from_metrics`] followed by every block enabled in `features`.
+pub fn features_from_metrics_with(data: &[&TextMetrics], features: &FeatureSet) -> Array2<f64> {
+    let mut blocks = vec![features_from_metrics(data)];
 
-        // slow but fine, only one.
-        let html_escapes = text.matches("&amp;").count();
+    if features.rhythm {
+        blocks.push(rhythm_features(data));
+    }
 
-        let dev_log = self.devlog_ahocorasick.find_iter(&text).count();
+    if features.lexical {
+        blocks.push(lexical_features(data));
+    }
 
-        let buzzwords = self.buzzword_ahocorasick.find_iter(&text).count()
-            - self.negative_buzzword_ahocorasick.find_iter(&text).count();
+    if let Some(words) = &features.function_words {
+        blocks.push(function_word_features(data, words.len()));
+    }
 
-        let not_just = self.not_just_ahocorasick.find_iter(&text).count();
+    if features.punctuation {
+        blocks.push(punctuation_features(data));
+    }
 
-        let backstory = self.backstory_ahocorasick.find_iter(&text).count();
-        let incper = self
-            .incorrect_perspective_ahocorasick
-            .find_iter(&text)
-            .count();
+    if features.perplexity {
+        blocks.push(perplexity_features(data));
+    }
 
-        let fancy = self.mr_fancy_pants_ahocorasick.find_iter(&text).count();
+    if features.embeddings > 0 {
+        blocks.push(embedding_features(data, features.embeddings));
+    }
+
+    let views: Vec<_> = blocks.iter().map(|block| block.view()).collect();
+    concatenate(Axis(1), &views).expect("blocks have one row per sample")
+}
+
+/// Sentence and paragraph rhythm columns, not part of the default layout so
+/// existing models are unaffected.
+pub fn rhythm_features(data: &[&TextMetrics]) -> Array2<f64> {
+    let n_features = 6;
+    let n_samples = data.len();
+
+    let mut array = Array2::<f64>::zeros((n_samples, n_features));
 
-        TextMetrics {
-            emoji_rate: (emoji_count * 5) as f64 / sc,
-            buzzword_rate: (buzzwords * 2) as f64 / sc,
-            backstory_count: backstory as f64,
-            incorrect_perspective_count: incper as f64,
-            mr_fancy_pants: fancy as f64,
+    for (i, sample) in data.iter().enumerate() {
+        array[[i, 0]] = sample.sentence_length_mean;
+        array[[i, 1]] = sample.sentence_length_variance;
+        array[[i, 2]] = sample.sentence_length_cv;
+        array[[i, 3]] = sample.paragraph_length_mean;
+        array[[i, 4]] = sample.paragraph_length_variance;
+        array[[i, 5]] = sample.paragraph_length_cv;
+    }
 
-            devlog_count: dev_log as f64,
-            html_escape_count: html_escapes as f64,
-            not_just_count: not_just as f64,
+    array
+}
 
-            irregular_quotations: irr_quote as f64,
-            irregular_dashes: irr_dash as f64,
-            irregular_arrows: irr_arr as f64,
-            irregular_ellipsis: irr_ell as f64,
-            irregular_markdown: markdown as f64,
+/// Lexical diversity and readability columns, zero for samples calculated
+/// without [`FeatureSet::lexical`].
+pub fn lexical_features(data: &[&TextMetrics]) -> Array2<f64> {
+    let n_features = 7;
+    let n_samples = data.len();
+
+    let mut array = Array2::<f64>::zeros((n_samples, n_features));
 
-            labels: labels as f64,
-            hashtags: hashtags as f64,
+    for (i, sample) in data.iter().enumerate() {
+        let Some(lexical) = &sample.lexical else {
+            continue;
+        };
+
+        array[[i, 0]] = lexical.type_token_ratio;
+        array[[i, 1]] = lexical.mtld;
+        array[[i, 2]] = lexical.hdd;
+        array[[i, 3]] = lexical.avg_word_length;
+        array[[i, 4]] = lexical.long_word_ratio;
+        array[[i, 5]] = lexical.flesch_reading_ease;
+        array[[i, 6]] = lexical.flesch_kincaid_grade;
+    }
+
+    array
+}
+
+/// Function word frequency columns, zero for samples calculated without
+/// [`FeatureSet::function_words`].
+pub fn function_word_features(data: &[&TextMetrics], n_words: usize) -> Array2<f64> {
+    let n_samples = data.len();
+
+    let mut array = Array2::<f64>::zeros((n_samples, n_words));
+
+    for (i, sample) in data.iter().enumerate() {
+        let Some(frequencies) = &sample.function_words else {
+            continue;
+        };
+
+        for (j, &freq) in frequencies.iter().take(n_words).enumerate() {
+            array[[i, j]] = freq;
         }
     }
+
+    array
+}
+
+/// Punctuation profile columns, zero for samples calculated without
+/// [`FeatureSet::punctuation`].
+pub fn punctuation_features(data: &[&TextMetrics]) -> Array2<f64> {
+    let n_features = 9;
+    let n_samples = data.len();
+
+    let mut array = Array2::<f64>::zeros((n_samples, n_features));
+
+    for (i, sample) in data.iter().enumerate() {
+        let Some(punctuation) = &sample.punctuation else {
+            continue;
+        };
+
+        array[[i, 0]] = punctuation.commas;
+        array[[i, 1]] = punctuation.periods;
+        array[[i, 2]] = punctuation.colons;
+        array[[i, 3]] = punctuation.semicolons;
+        array[[i, 4]] = punctuation.exclamations;
+        array[[i, 5]] = punctuation.questions;
+        array[[i, 6]] = punctuation.parentheticals;
+        array[[i, 7]] = punctuation.oxford_commas;
+        array[[i, 8]] = punctuation.space_before_punctuation;
+    }
+
+    array
 }
 
-pub fn features_from_metrics(data: &[&TextMetrics]) -> Array2<f64> {
-    let n_features = 15;
+/// Language model perplexity columns, zero for samples calculated without
+/// [`FeatureSet::perplexity`].
+pub fn perplexity_features(data: &[&TextMetrics]) -> Array2<f64> {
+    let n_features = 2;
     let n_samples = data.len();
 
     let mut array = Array2::<f64>::zeros((n_samples, n_features));
 
     for (i, sample) in data.iter().enumerate() {
-        array[[i, 0]] = sample.emoji_rate * 2.;
-        array[[i, 1]] = sample.buzzword_rate * 10.;
-        array[[i, 2]] = sample.irregular_dashes * 20.;
-        array[[i, 3]] = sample.irregular_quotations * 5.;
-        array[[i, 4]] = sample.labels;
-        array[[i, 5]] = sample.irregular_ellipsis;
-        array[[i, 6]] = sample.html_escape_count * 5.;
-        array[[i, 7]] = sample.not_just_count * 5.;
-        array[[i, 8]] = sample.devlog_count;
-        array[[i, 9]] = sample.irregular_markdown;
-        array[[i, 10]] = sample.hashtags;
-        array[[i, 11]] = sample.mr_fancy_pants;
-        array[[i, 12]] = sample.incorrect_perspective_count;
-        array[[i, 13]] = sample.backstory_count;
-        array[[i, 14]] = sample.irregular_arrows * 20.;
+        let Some(perplexity) = &sample.perplexity else {
+            continue;
+        };
+
+        array[[i, 0]] = perplexity.perplexity;
+        array[[i, 1]] = perplexity.sentence_variance;
     }
 
     array
 }
 
+/// Reduced sentence embedding columns, zero for samples calculated without an
+/// embedder.
+pub fn embedding_features(data: &[&TextMetrics], dims: usize) -> Array2<f64> {
+    let n_samples = data.len();
+
+    let mut array = Array2::<f64>::zeros((n_samples, dims));
+
+    for (i, sample) in data.iter().enumerate() {
+        let Some(embedding) = &sample.embedding else {
+            continue;
+        };
+
+        for (j, &value) in embedding.iter().take(dims).enumerate() {
+            array[[i, j]] = value;
+        }
+    }
+
+    array
+}
+
+/// Mean, population variance and coefficient of variation.
+fn dispersion(lengths: &[usize]) -> (f64, f64, f64) {
+    if lengths.is_empty() {
+        return (0., 0., 0.);
+    }
+
+    let n = lengths.len() as f64;
+    let mean = lengths.iter().sum::<usize>() as f64 / n;
+    let variance = lengths
+        .iter()
+        .map(|&len| (len as f64 - mean).powi(2))
+        .sum::<f64>()
+        / n;
+
+    let cv = if mean > 0. {
+        variance.sqrt() / mean
+    } else {
+        0.
+    };
+
+    (mean, variance, cv)
+}
+
+/// `distance` must be what the `centroids` were fitted with, see [`Clusterer`].
 pub fn point_confidence(
-    model: &KMeans<f64, DistanceFunction>,
+    centroids: ArrayView2<f64>,
+    distance: &DistanceFunction,
     observation: ArrayView1<f64>,
 ) -> (Array1<f64>, Array1<f64>) {
-    let centroids = model.centroids();
     let distances = centroids
         .axis_iter(Axis(0))
-        .map(|centroid_row| DIST_FN.distance(observation, centroid_row))
+        .map(|centroid_row| distance.distance(observation, centroid_row))
         .collect::<Array1<_>>();
 
     let mut sims = distances.mapv(|d| 1.0 / (1.0 + d));
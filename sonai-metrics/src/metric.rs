@@ -0,0 +1,337 @@
+use serde::Serialize;
+use serde::ser::SerializeMap;
+use std::fmt;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::normalize;
+use crate::punctuation::PunctuationCounts;
+
+/// A single scalar measured on a [`Document`].
+///
+/// Registered metrics become a column of the feature matrix, an entry in the
+/// serialized [`crate::TextMetrics`] and a cell of its `Display`, in
+/// registration order.
+pub trait Metric: Send + Sync {
+    /// Short snake_case name, used as the serialized key and display label.
+    fn name(&self) -> &'static str;
+
+    fn compute(&self, doc: &Document) -> f64;
+
+    /// Column weight for [`crate::ScalerKind::Fixed`], fitted scalers ignore it.
+    fn scale(&self) -> f64 {
+        1.
+    }
+}
+
+/// Text pre-processed once and shared by every metric.
+#[derive(Debug)]
+pub struct Document<'a> {
+    /// As typed, typography metrics look here
+    pub raw: &'a str,
+    /// [`normalize`]d, lexicons look here
+    pub normalized: String,
+    /// Normalized and trimmed with blank lines collapsed
+    pub text: String,
+    /// `text` on a single line
+    pub flat: String,
+    /// Words per sentence, empty sentences dropped
+    pub sentence_lengths: Vec<usize>,
+    /// Words per blank-line separated paragraph
+    pub paragraph_lengths: Vec<usize>,
+    /// Raw chars outside emoji, each with the next char in its grapheme
+    chars: Vec<(char, Option<char>)>,
+    /// Tallied in the same pass as `chars`
+    pub(crate) punctuation: PunctuationCounts,
+}
+
+impl<'a> Document<'a> {
+    pub fn new(raw: &'a str) -> Self {
+        let normalized = normalize(raw);
+        let text = normalized.trim().replace("\n\n", "\n");
+        let flat = text.replace("\n", " ").replace("  ", " ");
+
+        // lengths in words, humans ramble then fire off one-liners
+        let sentence_lengths = text
+            .unicode_sentences()
+            .map(|sentence| sentence.unicode_words().count())
+            .filter(|&words| words > 0)
+            .collect();
+
+        let mut paragraph_lengths = Vec::new();
+        let mut paragraph = 0usize;
+
+        for line in normalized.lines() {
+            if line.trim().is_empty() {
+                if paragraph > 0 {
+                    paragraph_lengths.push(paragraph);
+                }
+                paragraph = 0;
+            } else {
+                paragraph += line.unicode_words().count();
+            }
+        }
+
+        if paragraph > 0 {
+            paragraph_lengths.push(paragraph);
+        }
+
+        // the one walk over what was typed, char classes and punctuation
+        // both read from it
+        let mut chars = Vec::with_capacity(raw.len());
+        let mut punctuation = PunctuationCounts::default();
+        let mut previous = None;
+
+        for grapheme in raw.graphemes(true) {
+            if emojis::get(grapheme).is_some() {
+                continue;
+            }
+
+            let mut grapheme = grapheme.chars().peekable();
+            while let Some(c) = grapheme.next() {
+                chars.push((c, grapheme.peek().copied()));
+                punctuation.observe(previous, c);
+                previous = Some(c);
+            }
+        }
+
+        Self {
+            raw,
+            normalized,
+            text,
+            flat,
+            sentence_lengths,
+            paragraph_lengths,
+            chars,
+            punctuation,
+        }
+    }
+
+    /// Never 0, rates are per sentence.
+    pub fn sentence_count(&self) -> usize {
+        self.sentence_lengths.len().max(1)
+    }
+
+    /// Sentences as the legacy metrics counted them, split on `.!?` and
+    /// newlines. The emoji and buzzword rates keep it so models fitted on
+    /// them still see what they were trained on. Never 0.
+    pub fn legacy_sentence_count(&self) -> usize {
+        self.text
+            .split(['.', '!', '?', '\n'])
+            .filter(|sentence| !sentence.trim().is_empty())
+            .count()
+            .max(1)
+    }
+
+    pub fn words(&self) -> Vec<&str> {
+        self.text.unicode_words().collect()
+    }
+
+    /// Graphemes of the raw text that are emoji.
+    pub fn emoji(&self) -> impl Iterator<Item = &str> {
+        self.raw
+            .graphemes(true)
+            .filter(|grapheme| emojis::get(grapheme).is_some())
+    }
+
+    /// Chars of the raw text outside emoji, each with the char after it in
+    /// the same grapheme.
+    pub fn chars(&self) -> impl Iterator<Item = (char, Option<char>)> {
+        self.chars.iter().copied()
+    }
+}
+
+/// Ordered set of metrics a [`crate::TextMetricFactory`] computes.
+#[derive(Default)]
+pub struct MetricRegistry {
+    metrics: Vec<Box<dyn Metric>>,
+}
+
+impl MetricRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `metric`, it gets the next feature column.
+    pub fn register(&mut self, metric: impl Metric + 'static) {
+        self.metrics.push(Box::new(metric));
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'static str> {
+        self.metrics.iter().map(|metric| metric.name())
+    }
+
+    pub fn scales(&self) -> impl Iterator<Item = f64> {
+        self.metrics.iter().map(|metric| metric.scale())
+    }
+
+    pub fn len(&self) -> usize {
+        self.metrics.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.metrics.is_empty()
+    }
+
+    pub fn compute(&self, doc: &Document) -> MetricValues {
+        MetricValues(
+            self.metrics
+                .iter()
+                .map(|metric| MetricValue {
+                    name: metric.name(),
+                    value: metric.compute(doc),
+                })
+                .collect(),
+        )
+    }
+}
+
+impl fmt::Debug for MetricRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MetricValue {
+    pub name: &'static str,
+    pub value: f64,
+}
+
+/// Registry output in column order, serializes as `name: value`.
+#[derive(Debug, Clone, Default)]
+pub struct MetricValues(pub Vec<MetricValue>);
+
+impl MetricValues {
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.0
+            .iter()
+            .find(|metric| metric.name == name)
+            .map(|metric| metric.value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &MetricValue> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Serialize for MetricValues {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for metric in &self.0 {
+            map.serialize_entry(metric.name, &metric.value)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::{TextMetricFactory, features_from_metrics};
+
+    struct Shouting;
+
+    impl Metric for Shouting {
+        fn name(&self) -> &'static str {
+            "shouting"
+        }
+
+        fn compute(&self, doc: &Document) -> f64 {
+            doc.raw.matches('!').count() as f64
+        }
+
+        fn scale(&self) -> f64 {
+            3.
+        }
+    }
+
+    #[test]
+    fn builtins_keep_the_legacy_order() {
+        let registry = MetricRegistry::builtin().unwrap();
+
+        assert_eq!(
+            registry.names().collect::<Vec<_>>(),
+            [
+                "emoji",
+                "buzzword",
+                "irr_dash",
+                "irr_quote",
+                "labels",
+                "irr_ell",
+                "html",
+                "not_just",
+                "devlog",
+                "irr_md",
+                "hashtags",
+                "fancy",
+                "bad_per",
+                "backstory",
+                "irr_arr",
+                "invisible",
+                "homoglyph",
+            ]
+        );
+    }
+
+    #[test]
+    fn with_metric_appends_a_column() {
+        let builtin = TextMetricFactory::new().unwrap();
+        let factory = TextMetricFactory::new().unwrap().with_metric(Shouting);
+
+        assert_eq!(factory.n_features(), builtin.n_features() + 1);
+        assert_eq!(factory.registry().names().last(), Some("shouting"));
+        assert_eq!(factory.weights().last(), Some(&3.));
+
+        let text = "it works!! finally!";
+        let metrics = factory.calculate(text).unwrap();
+        let legacy = builtin.calculate(text).unwrap();
+        let features = features_from_metrics(&[&metrics]);
+
+        assert_eq!(features.ncols(), factory.n_features());
+        assert_eq!(features[[0, builtin.n_features()]], 3.);
+        assert_eq!(
+            features
+                .row(0)
+                .iter()
+                .take(builtin.n_features())
+                .collect::<Vec<_>>(),
+            features_from_metrics(&[&legacy])
+                .row(0)
+                .iter()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn values_serialize_as_a_map() {
+        let values = MetricValues(vec![
+            MetricValue {
+                name: "emoji",
+                value: 1.,
+            },
+            MetricValue {
+                name: "buzzword",
+                value: 2.5,
+            },
+        ]);
+
+        let json = serde_json::to_string(&values).unwrap();
+        assert_eq!(json, r#"{"emoji":1.0,"buzzword":2.5}"#);
+
+        let map: BTreeMap<String, f64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(map.len(), values.len());
+        for metric in values.iter() {
+            assert_eq!(map.get(metric.name), Some(&metric.value));
+        }
+    }
+}
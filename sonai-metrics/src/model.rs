@@ -0,0 +1,196 @@
+use linfa_clustering::KMeans;
+use linfa_nn::distance::Distance;
+use ndarray::{Array1, Array2, ArrayView1, ArrayView2, Axis};
+use serde::{Deserialize, Serialize};
+
+use crate::{DistanceFunction, FeatureSet, GaussianMixture, NgramModel, Pca, Scaler};
+
+/// Everything inference needs, written by training-bin to `sonai/model.sonai`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Model {
+    /// Registry metrics the model was fitted on, in column order
+    pub metrics: Vec<String>,
+    pub features: FeatureSet,
+    pub scaler: Scaler,
+    pub language_model: Option<NgramModel>, // only with FeatureSet::perplexity
+    pub pca: Option<Pca>,                   // only with FeatureSet::embeddings
+    pub clusterer: Clusterer,
+    /// One per cluster, several clusters can share a label
+    pub labels: Vec<Label>,
+}
+
+impl Model {
+    /// Where the model's columns sit in rows led by the registry metrics
+    /// `names` and followed by the `layout` blocks. Metrics are found by name,
+    /// registering a new one doesn't shift anything.
+    pub fn columns(&self, names: &[&str], layout: &FeatureSet) -> Result<Vec<usize>, String> {
+        let mut columns = self
+            .metrics
+            .iter()
+            .map(|metric| {
+                names
+                    .iter()
+                    .position(|name| name == metric)
+                    .ok_or_else(|| format!("the model needs the `{metric}` metric"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        columns.extend(
+            layout
+                .columns_of(&self.features, names.len())
+                .into_iter()
+                .skip(names.len()),
+        );
+
+        if columns.len() != self.clusterer.n_features() {
+            return Err(format!(
+                "the model was fitted on {} columns but its layout has {}",
+                self.clusterer.n_features(),
+                columns.len()
+            ));
+        }
+
+        Ok(columns)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Label {
+    Ai,
+    Human,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Clusterer {
+    KMeans {
+        kmeans: KMeans<f64, DistanceFunction>,
+        /// Same as the one `kmeans` was fitted with, linfa keeps its copy private
+        distance: DistanceFunction,
+    },
+    Gmm(GaussianMixture),
+    /// Seeded KMeans with the labeled texts pinned to clusters of their label,
+    /// fitted by training-bin since linfa can't constrain assignments
+    Seeded {
+        centroids: Array2<f64>,
+        distance: DistanceFunction,
+    },
+}
+
+impl Clusterer {
+    /// Columns the clusters were fitted on.
+    pub fn n_features(&self) -> usize {
+        match self {
+            Self::KMeans { kmeans, .. } => kmeans.centroids().ncols(),
+            Self::Gmm(gmm) => gmm.n_features(),
+            Self::Seeded { centroids, .. } => centroids.ncols(),
+        }
+    }
+
+    pub fn n_clusters(&self) -> usize {
+        match self {
+            Self::KMeans { kmeans, .. } => kmeans.centroids().nrows(),
+            Self::Gmm(gmm) => gmm.n_components(),
+            Self::Seeded { centroids, .. } => centroids.nrows(),
+        }
+    }
+
+    /// Centroids, or component means for the mixture.
+    pub fn centers(&self) -> ArrayView2<'_, f64> {
+        match self {
+            Self::KMeans { kmeans, .. } => kmeans.centroids().view(),
+            Self::Gmm(gmm) => gmm.means(),
+            Self::Seeded { centroids, .. } => centroids.view(),
+        }
+    }
+
+    /// Cluster of each row, nearest centroid for KMeans.
+    pub fn predict(&self, features: ArrayView2<f64>) -> Array1<usize> {
+        match self {
+            Self::Gmm(gmm) => gmm.predict(features),
+            Self::KMeans { kmeans, distance } => features
+                .axis_iter(Axis(0))
+                .map(|x| nearest_centroid(kmeans.centroids().view(), distance, x))
+                .collect(),
+            Self::Seeded {
+                centroids,
+                distance,
+            } => features
+                .axis_iter(Axis(0))
+                .map(|x| nearest_centroid(centroids.view(), distance, x))
+                .collect(),
+        }
+    }
+}
+
+/// Index of the centroid closest to `x`.
+pub fn nearest_centroid(
+    centroids: ArrayView2<f64>,
+    distance: &DistanceFunction,
+    x: ArrayView1<f64>,
+) -> usize {
+    centroids
+        .axis_iter(Axis(0))
+        .map(|centroid| distance.distance(x, centroid))
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map_or(0, |(cluster, _)| cluster)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Scaler, ScalerKind};
+
+    fn model(metrics: &[&str], features: FeatureSet, width: usize) -> Model {
+        Model {
+            metrics: metrics.iter().map(|name| name.to_string()).collect(),
+            features,
+            scaler: Scaler::fit(ScalerKind::Fixed, Array2::zeros((0, width)).view(), &[]),
+            language_model: None,
+            pca: None,
+            clusterer: Clusterer::Seeded {
+                centroids: Array2::zeros((2, width)),
+                distance: DistanceFunction::L2,
+            },
+            labels: vec![Label::Ai, Label::Human],
+        }
+    }
+
+    #[test]
+    fn metrics_are_found_by_name() {
+        let model = model(&["b", "a"], FeatureSet::default(), 2);
+
+        assert_eq!(
+            model.columns(&["a", "new", "b"], &FeatureSet::default()),
+            Ok(vec![2, 0])
+        );
+    }
+
+    #[test]
+    fn blocks_follow_the_registry() {
+        let layout = FeatureSet {
+            rhythm: true,
+            lexical: true,
+            ..FeatureSet::default()
+        };
+        let features = FeatureSet {
+            lexical: true,
+            ..FeatureSet::default()
+        };
+        let model = model(&["a"], features, 8);
+
+        assert_eq!(
+            model.columns(&["a", "b"], &layout),
+            Ok([0].into_iter().chain(8..15).collect())
+        );
+    }
+
+    #[test]
+    fn mismatched_layouts_are_errors() {
+        let missing = model(&["a", "gone"], FeatureSet::default(), 2);
+        assert!(missing.columns(&["a"], &FeatureSet::default()).is_err());
+
+        let too_wide = model(&["a"], FeatureSet::default(), 3);
+        assert!(too_wide.columns(&["a"], &FeatureSet::default()).is_err());
+    }
+}
@@ -0,0 +1,303 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::normalize;
+
+const DISCOUNT: f64 = 0.75;
+const SEPARATOR: char = '\u{1F}';
+const START: &str = "\u{2}";
+const END: &str = "\u{3}";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NgramUnit {
+    Char,
+    Word,
+}
+
+/// Interpolated Kneser-Ney language model over characters or words.
+///
+/// Fit it on text that is known to be human, generated text reads as
+/// unusually smooth (low perplexity) under it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NgramModel {
+    order: usize,
+    unit: NgramUnit,
+    // [k - 1]: raw counts for the highest order, continuation counts below it,
+    // ordered so the same fit always encodes to the same bytes
+    ngrams: Vec<BTreeMap<String, u32>>,
+    // [k - 1]: context of a k-gram -> (summed count, distinct followers)
+    contexts: Vec<BTreeMap<String, (u32, u32)>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Perplexity {
+    pub perplexity: f64,        // whole text
+    pub sentence_variance: f64, // variance of per-sentence perplexity
+}
+
+impl NgramModel {
+    pub fn fit<I, S>(texts: I, order: usize, unit: NgramUnit) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        assert!(order > 0, "an n-gram model needs n > 0");
+
+        let mut top: BTreeMap<String, u32> = BTreeMap::new();
+
+        for text in texts {
+            for sentence in sentences(text.as_ref(), unit) {
+                let tokens = pad(&sentence, order);
+
+                for gram in tokens.windows(order) {
+                    *top.entry(gram.join(&SEPARATOR.to_string())).or_default() += 1;
+                }
+            }
+        }
+
+        // N1+(• g): in how many distinct left contexts a lower order gram shows up
+        let mut ngrams = vec![top];
+        for _ in 1..order {
+            let higher = ngrams.last().expect("seeded with the top order");
+            let mut continuation: BTreeMap<String, u32> = BTreeMap::new();
+
+            for gram in higher.keys() {
+                if let Some((_, suffix)) = gram.split_once(SEPARATOR) {
+                    *continuation.entry(suffix.to_string()).or_default() += 1;
+                }
+            }
+
+            ngrams.push(continuation);
+        }
+        ngrams.reverse();
+
+        let contexts = ngrams
+            .iter()
+            .map(|grams| {
+                let mut contexts: BTreeMap<String, (u32, u32)> = BTreeMap::new();
+
+                for (gram, &count) in grams {
+                    let context = gram
+                        .rsplit_once(SEPARATOR)
+                        .map_or("", |(context, _)| context);
+                    let entry = contexts.entry(context.to_string()).or_default();
+                    entry.0 += count;
+                    entry.1 += 1;
+                }
+
+                contexts
+            })
+            .collect();
+
+        Self {
+            order,
+            unit,
+            ngrams,
+            contexts,
+        }
+    }
+
+    /// Perplexity of the whole text and how much it swings between sentences.
+    pub fn perplexity(&self, text: &str) -> Perplexity {
+        let mut log_prob = 0.;
+        let mut tokens = 0usize;
+        let mut per_sentence = Vec::new();
+
+        for sentence in sentences(text, self.unit) {
+            let padded = pad(&sentence, self.order);
+            let mut sentence_log_prob = 0.;
+
+            for gram in padded.windows(self.order) {
+                let (token, history) = gram.split_last().expect("order > 0");
+                sentence_log_prob += self.probability(history, token).ln();
+            }
+
+            let n = padded.len() + 1 - self.order;
+            per_sentence.push((-sentence_log_prob / n as f64).exp());
+
+            log_prob += sentence_log_prob;
+            tokens += n;
+        }
+
+        if tokens == 0 {
+            return Perplexity {
+                perplexity: 0.,
+                sentence_variance: 0.,
+            };
+        }
+
+        let mean = per_sentence.iter().sum::<f64>() / per_sentence.len() as f64;
+        let sentence_variance = per_sentence
+            .iter()
+            .map(|ppl| (ppl - mean).powi(2))
+            .sum::<f64>()
+            / per_sentence.len() as f64;
+
+        Perplexity {
+            perplexity: (-log_prob / tokens as f64).exp(),
+            sentence_variance,
+        }
+    }
+
+    fn probability(&self, history: &[String], token: &str) -> f64 {
+        let k = history.len();
+        let separator = SEPARATOR.to_string();
+
+        let lower = match history.split_first() {
+            Some((_, shorter)) => self.probability(shorter, token),
+            // unseen tokens fall back to uniform over the vocabulary + 1
+            None => 1. / (self.ngrams[0].len() + 1) as f64,
+        };
+
+        let context = history.join(&separator);
+        let Some(&(total, followers)) = self.contexts[k].get(&context) else {
+            return lower;
+        };
+
+        let gram = if k == 0 {
+            token.to_string()
+        } else {
+            format!("{context}{SEPARATOR}{token}")
+        };
+
+        let count = self.ngrams[k].get(&gram).copied().unwrap_or(0) as f64;
+
+        ((count - DISCOUNT).max(0.) + DISCOUNT * followers as f64 * lower) / total as f64
+    }
+}
+
+fn sentences(text: &str, unit: NgramUnit) -> Vec<Vec<String>> {
+    // the separator and padding markers are ours, text can't forge them
+    let text = normalize(text).replace(
+        |c| c == SEPARATOR || START.contains(c) || END.contains(c),
+        "",
+    );
+
+    text.unicode_sentences()
+        .map(|sentence| match unit {
+            NgramUnit::Char => sentence
+                .trim()
+                .chars()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>(),
+            NgramUnit::Word => sentence.unicode_words().map(str::to_string).collect(),
+        })
+        .filter(|tokens| !tokens.is_empty())
+        .collect()
+}
+
+fn pad(tokens: &[String], order: usize) -> Vec<String> {
+    let mut padded = vec![START.to_string(); order - 1];
+    padded.extend_from_slice(tokens);
+    padded.push(END.to_string());
+    padded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORPUS: &[&str] = &[
+        "I fixed the parser today. It was slow.",
+        "The parser was slow, so I fixed it.",
+        "Today I rewrote the tests.",
+    ];
+
+    fn history(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn markers_are_stripped_from_the_input() {
+        assert_eq!(
+            sentences("pa\u{1F}rser \u{2}was\u{3} slow", NgramUnit::Word),
+            [history(&["parser", "was", "slow"])]
+        );
+        assert_eq!(
+            sentences("a\u{2}\u{1F}b", NgramUnit::Char),
+            [history(&["a", "b"])]
+        );
+
+        let model = NgramModel::fit(CORPUS, 3, NgramUnit::Word);
+        assert_eq!(
+            model.perplexity("the parser\u{1F}was slow").perplexity,
+            model.perplexity("the parserwas slow").perplexity
+        );
+    }
+
+    #[test]
+    fn probabilities_sum_to_one() {
+        for unit in [NgramUnit::Word, NgramUnit::Char] {
+            let model = NgramModel::fit(CORPUS, 3, unit);
+            // every token seen, plus one that never was
+            let vocabulary: Vec<&str> = model.ngrams[0]
+                .keys()
+                .map(String::as_str)
+                .chain(["unseen"])
+                .collect();
+
+            let histories = match unit {
+                NgramUnit::Word => [
+                    history(&[START, START]),
+                    history(&["the", "parser"]),
+                    history(&["i", "fixed"]),
+                    history(&["never", "seen"]),
+                ],
+                NgramUnit::Char => [
+                    history(&[START, START]),
+                    history(&["t", "h"]),
+                    history(&["e", " "]),
+                    history(&["q", "z"]),
+                ],
+            };
+
+            for history in histories {
+                let total: f64 = vocabulary
+                    .iter()
+                    .map(|token| model.probability(&history, token))
+                    .sum();
+
+                assert!((total - 1.).abs() < 1e-9, "{unit:?} {history:?}: {total}");
+            }
+        }
+    }
+
+    #[test]
+    fn unigram_perplexity_by_hand() {
+        // tokens a, b and the end marker once each: (1 - 0.75 + 0.75 * 3 / 4) / 3
+        let model = NgramModel::fit(["a b."], 1, NgramUnit::Word);
+        let p: f64 = (1. - DISCOUNT + DISCOUNT * 3. / 4.) / 3.;
+
+        let perplexity = model.perplexity("a b.");
+        assert!((perplexity.perplexity - 1. / p).abs() < 1e-9);
+        assert_eq!(perplexity.sentence_variance, 0.);
+    }
+
+    #[test]
+    fn seen_text_is_less_surprising() {
+        let model = NgramModel::fit(CORPUS, 3, NgramUnit::Word);
+
+        let seen = model.perplexity("I fixed the parser today.").perplexity;
+        let unseen = model
+            .perplexity("Quantum llamas juggle violet spreadsheets.")
+            .perplexity;
+
+        assert!(seen < unseen, "{seen} >= {unseen}");
+        assert_eq!(model.perplexity("").perplexity, 0.);
+    }
+
+    #[test]
+    fn encodes_the_same_twice() {
+        let encode = |model: &NgramModel| {
+            bincode::serde::encode_to_vec(model, bincode::config::standard()).unwrap()
+        };
+
+        for unit in [NgramUnit::Word, NgramUnit::Char] {
+            assert_eq!(
+                encode(&NgramModel::fit(CORPUS, 3, unit)),
+                encode(&NgramModel::fit(CORPUS, 3, unit))
+            );
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use unicode_normalization::UnicodeNormalization;
+use unicode_script::{Script, UnicodeScript};
+use unicode_security::skeleton;
+
+/// Folds text into the form the lexicons are matched against.
+///
+/// Invisible format characters are stripped, the text is NFKC normalized,
+/// [`fold_confusables`]d and Unicode case folded, and curly apostrophes/quotes
+/// collapse into their ASCII counterparts so a single `"it's not just"`
+/// pattern covers every variant.
+///
+/// Typography metrics must look at the raw text instead, this throws away
+/// exactly the characters they count.
+pub fn normalize(text: &str) -> String {
+    let stripped: String = text.chars().filter(|&c| !is_invisible(c)).collect();
+
+    // Case folding is not closed under NFKC, so normalize again afterwards
+    let unspoofed = fold_confusables(&stripped.nfkc().collect::<String>());
+    let folded = caseless::default_case_fold_str(&unspoofed);
+
+    folded.nfkc().map(canonical_quote).collect()
+}
+
+/// Swaps the letters of other scripts inside a Latin word for their UTS #39
+/// skeleton, so the Greek capitals in `ΜΟDERN` become `MO`. Words written
+/// entirely in another script are left alone.
+pub fn fold_confusables(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find(char::is_alphabetic) {
+        folded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = rest
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(rest.len());
+        let word = &rest[..end];
+        rest = &rest[end..];
+
+        let foreign = |c: char| {
+            !matches!(
+                c.script(),
+                Script::Latin | Script::Common | Script::Inherited
+            )
+        };
+        if !word.chars().any(|c| c.script() == Script::Latin) || !word.chars().any(foreign) {
+            folded.push_str(word);
+            continue;
+        }
+
+        for c in word.chars() {
+            if foreign(c) {
+                folded.extend(skeleton(c.encode_utf8(&mut [0; 4])));
+            } else {
+                folded.push(c);
+            }
+        }
+    }
+
+    folded.push_str(rest);
+    folded
+}
+
+/// Zero-width, bidi control and other format characters that render as
+/// nothing but break substring matching.
+pub fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}' // soft hyphen
+            | '\u{034F}' // combining grapheme joiner
+            | '\u{061C}' // arabic letter mark
+            | '\u{180E}' // mongolian vowel separator
+            | '\u{200B}'..='\u{200F}' // zero-width space/joiners, lrm, rlm
+            | '\u{202A}'..='\u{202E}' // bidi embeddings and overrides
+            | '\u{2060}'..='\u{2064}' // word joiner, invisible operators
+            | '\u{2066}'..='\u{2069}' // bidi isolates
+            | '\u{FEFF}' // bom / zero-width no-break space
+    )
+}
+
+/// Maps apostrophe and quotation mark look-alikes onto `'` and `"`.
+pub fn canonical_quote(c: char) -> char {
+    match c {
+        '‘' | '’' | '‚' | '‛' | 'ʼ' | '′' => '\'',
+        '“' | '”' | '„' | '‟' | '″' => '"',
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_mixed_script_spoofs() {
+        // Greek capital mu and omicron
+        assert_eq!(normalize("\u{039C}\u{039F}DERN"), "modern");
+        // Cyrillic a and e
+        assert_eq!(normalize("s\u{0430}f\u{0435}"), "safe");
+    }
+
+    #[test]
+    fn keeps_other_scripts() {
+        assert_eq!(normalize("Μόνο ΜΟΝΟ"), normalize("μόνο μονο"));
+        assert_eq!(fold_confusables("привет мир"), "привет мир");
+    }
+
+    #[test]
+    fn case_width_quotes_and_invisibles() {
+        assert_eq!(normalize("ＭＯＤＥＲＮ"), "modern");
+        assert_eq!(normalize("it\u{2019}s not j\u{200B}ust"), "it's not just");
+        assert_eq!(normalize("STRASSE"), normalize("straße"));
+    }
+}
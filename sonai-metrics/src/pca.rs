@@ -0,0 +1,113 @@
+use ndarray::{Array1, Array2, ArrayView2, Axis};
+use serde::{Deserialize, Serialize};
+
+const POWER_ITERATIONS: usize = 200;
+
+/// Principal component projection, used to shrink sentence embeddings down
+/// to a handful of columns before they sit next to the handcrafted features.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pca {
+    mean: Array1<f64>,
+    components: Array2<f64>, // one row per component
+}
+
+impl Pca {
+    /// Top `k` components by power iteration with deflation, deterministic and
+    /// plenty for a few hundred dimensions. Errors when the data has fewer than
+    /// `k` columns.
+    pub fn fit(data: ArrayView2<f64>, k: usize) -> Result<Self, String> {
+        let (n, d) = data.dim();
+        if k > d {
+            return Err(format!(
+                "can't project {d} dimensional embeddings onto {k} components"
+            ));
+        }
+
+        let mean = data.mean_axis(Axis(0)).unwrap_or_else(|| Array1::zeros(d));
+        let centered = &data - &mean;
+        let mut covariance = centered.t().dot(&centered) / (n.max(2) - 1) as f64;
+
+        let mut components = Array2::zeros((k, d));
+
+        for mut component in components.axis_iter_mut(Axis(0)) {
+            let mut v = Array1::from_elem(d, 1. / (d as f64).sqrt());
+
+            for _ in 0..POWER_ITERATIONS {
+                let next = covariance.dot(&v);
+                let norm = next.dot(&next).sqrt();
+
+                if norm == 0. {
+                    break;
+                }
+
+                v = next / norm;
+            }
+
+            let eigenvalue = v.dot(&covariance.dot(&v));
+            let column = v.view().insert_axis(Axis(1));
+            covariance -= &(column.dot(&column.t()) * eigenvalue);
+
+            component.assign(&v);
+        }
+
+        Ok(Self { mean, components })
+    }
+
+    pub fn n_components(&self) -> usize {
+        self.components.nrows()
+    }
+
+    pub fn transform(&self, data: ArrayView2<f64>) -> Array2<f64> {
+        (&data - &self.mean).dot(&self.components.t())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    // points along (3, 4) / 5, each pair spread a little across it
+    fn stretched() -> Array2<f64> {
+        Array2::from_shape_fn((40, 2), |(i, j)| {
+            let along = (i / 2) as f64 - 10.;
+            let across = if i % 2 == 0 { 0.5 } else { -0.5 };
+
+            [0.6 * along - 0.8 * across, 0.8 * along + 0.6 * across][j] + 10.
+        })
+    }
+
+    #[test]
+    fn finds_the_main_axis() {
+        let pca = Pca::fit(stretched().view(), 2).unwrap();
+        let first = pca.components.row(0);
+
+        // sign is arbitrary
+        assert!((first[0].abs() - 0.6).abs() < 1e-6, "{first}");
+        assert!((first[1].abs() - 0.8).abs() < 1e-6, "{first}");
+        assert!(pca.components.row(0).dot(&pca.components.row(1)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn transform_centres_and_rotates() {
+        let data = stretched();
+        let pca = Pca::fit(data.view(), 2).unwrap();
+        let projected = pca.transform(data.view());
+
+        for mean in projected.mean_axis(Axis(0)).unwrap() {
+            assert!(mean.abs() < 1e-9);
+        }
+
+        // a rotation keeps distances
+        let before = (&data.row(0) - &data.row(7)).mapv(|x| x * x).sum();
+        let after = (&projected.row(0) - &projected.row(7))
+            .mapv(|x| x * x)
+            .sum();
+        assert!((before - after).abs() < 1e-6);
+    }
+
+    #[test]
+    fn more_components_than_columns() {
+        assert!(Pca::fit(array![[1., 2.], [3., 4.]].view(), 3).is_err());
+    }
+}
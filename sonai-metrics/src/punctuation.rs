@@ -0,0 +1,118 @@
+use serde::Serialize;
+
+/// Punctuation use per 1000 characters.
+#[derive(Debug, Clone, Serialize)]
+pub struct PunctuationProfile {
+    pub commas: f64,
+    pub periods: f64,
+    pub colons: f64,
+    pub semicolons: f64,
+    pub exclamations: f64,
+    pub questions: f64,
+    pub parentheticals: f64,
+    pub oxford_commas: f64,            // a, b, and c
+    pub space_before_punctuation: f64, // word , word
+}
+
+/// Running counts, kept by [`crate::Document::new`] as it walks the chars.
+#[derive(Debug, Default)]
+pub(crate) struct PunctuationCounts {
+    chars: usize,
+    commas: usize,
+    periods: usize,
+    colons: usize,
+    semicolons: usize,
+    exclamations: usize,
+    questions: usize,
+    parentheticals: usize,
+    space_before_punctuation: usize,
+}
+
+impl PunctuationCounts {
+    pub(crate) fn observe(&mut self, previous: Option<char>, c: char) {
+        self.chars += 1;
+
+        match c {
+            ',' => self.commas += 1,
+            '.' => self.periods += 1,
+            ':' => self.colons += 1,
+            ';' => self.semicolons += 1,
+            '!' => self.exclamations += 1,
+            '?' => self.questions += 1,
+            '(' => self.parentheticals += 1,
+            _ => return,
+        }
+
+        // ` (` is just a parenthetical, and ` .env` and friends are everywhere in devlogs
+        if !matches!(c, '.' | '(') && previous.is_some_and(|p| p == ' ') {
+            self.space_before_punctuation += 1;
+        }
+    }
+
+    pub(crate) fn profile(&self, oxford_commas: usize) -> PunctuationProfile {
+        let per_mille = |count: usize| count as f64 * 1000. / self.chars.max(1) as f64;
+
+        PunctuationProfile {
+            commas: per_mille(self.commas),
+            periods: per_mille(self.periods),
+            colons: per_mille(self.colons),
+            semicolons: per_mille(self.semicolons),
+            exclamations: per_mille(self.exclamations),
+            questions: per_mille(self.questions),
+            parentheticals: per_mille(self.parentheticals),
+            oxford_commas: per_mille(oxford_commas),
+            space_before_punctuation: per_mille(self.space_before_punctuation),
+        }
+    }
+}
+
+/// `, and` / `, or` closing a list that already had a comma in the sentence.
+pub(crate) fn oxford_commas(sentence: &str) -> usize {
+    let mut count = 0;
+
+    for (i, _) in sentence.match_indices(", ") {
+        let rest = &sentence[i + 2..];
+        let closes_list = rest.starts_with("and ") || rest.starts_with("or ");
+
+        if closes_list && sentence[..i].contains(',') {
+            count += 1;
+        }
+    }
+
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FeatureSet, TextMetricFactory};
+
+    #[test]
+    fn rates_per_mille() {
+        // 50 chars: 3 commas (one after a space), 1 period, 1 colon,
+        // 1 semicolon, 4 exclamations, 1 question, 1 parenthetical and the
+        // oxford comma before "and c"
+        let text = "Wait , really? Yes: a, b, and c; done! (ok). Go!!!";
+        assert_eq!(text.chars().count(), 50);
+
+        let profile = TextMetricFactory::new()
+            .unwrap()
+            .with_features(FeatureSet {
+                punctuation: true,
+                ..FeatureSet::default()
+            })
+            .calculate(text)
+            .unwrap()
+            .punctuation
+            .unwrap();
+
+        assert_eq!(profile.commas, 60.);
+        assert_eq!(profile.periods, 20.);
+        assert_eq!(profile.colons, 20.);
+        assert_eq!(profile.semicolons, 20.);
+        assert_eq!(profile.exclamations, 80.);
+        assert_eq!(profile.questions, 20.);
+        assert_eq!(profile.parentheticals, 20.);
+        assert_eq!(profile.oxford_commas, 20.);
+        assert_eq!(profile.space_before_punctuation, 20.);
+    }
+}
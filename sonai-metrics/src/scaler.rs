@@ -0,0 +1,204 @@
+use ndarray::{Array1, Array2, ArrayView1, ArrayView2, Axis};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScalerKind {
+    /// Hand-picked [`crate::Metric::scale`] weights, what every model before
+    /// fitted scalers used
+    #[default]
+    Fixed,
+    ZScore,
+    /// Median and interquartile range, outliers don't drag the scale around
+    Robust,
+    MinMax,
+}
+
+impl ScalerKind {
+    pub const ALL: [Self; 4] = [Self::Fixed, Self::ZScore, Self::Robust, Self::MinMax];
+}
+
+impl FromStr for ScalerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fixed" => Ok(Self::Fixed),
+            "zscore" => Ok(Self::ZScore),
+            "robust" => Ok(Self::Robust),
+            "minmax" => Ok(Self::MinMax),
+            _ => Err(format!(
+                "unknown scaler `{s}`, expected fixed, zscore, robust or minmax"
+            )),
+        }
+    }
+}
+
+/// Per-column `(x - center) * weight`, fitted on the training matrix and
+/// shipped with the model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scaler {
+    center: Array1<f64>,
+    weight: Array1<f64>,
+}
+
+impl Scaler {
+    /// `weights` are the fixed column weights, only [`ScalerKind::Fixed`]
+    /// uses them. See [`crate::TextMetricFactory::weights`].
+    pub fn fit(kind: ScalerKind, features: ArrayView2<f64>, weights: &[f64]) -> Self {
+        let (center, spread): (Vec<f64>, Vec<f64>) = match kind {
+            ScalerKind::Fixed => {
+                return Self {
+                    center: Array1::zeros(weights.len()),
+                    weight: weights.iter().copied().collect(),
+                };
+            }
+            ScalerKind::ZScore => features
+                .axis_iter(Axis(1))
+                .map(|column| {
+                    let n = column.len().max(1) as f64;
+                    let mean = column.sum() / n;
+                    let variance = column.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+
+                    (mean, variance.sqrt())
+                })
+                .unzip(),
+            ScalerKind::Robust => features
+                .axis_iter(Axis(1))
+                .map(|column| {
+                    let sorted = sorted(column);
+                    let iqr = quantile(&sorted, 0.75) - quantile(&sorted, 0.25);
+
+                    (quantile(&sorted, 0.5), iqr)
+                })
+                .unzip(),
+            ScalerKind::MinMax => features
+                .axis_iter(Axis(1))
+                .map(|column| {
+                    let min = column.iter().copied().fold(f64::INFINITY, f64::min);
+                    let max = column.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+                    if min.is_finite() {
+                        (min, max - min)
+                    } else {
+                        (0., 1.)
+                    }
+                })
+                .unzip(),
+        };
+
+        // constant columns pass through instead of dividing by zero
+        let weight = spread
+            .into_iter()
+            .map(|spread| if spread > 0. { 1. / spread } else { 1. })
+            .collect();
+        let center = Array1::from(center);
+
+        Self { center, weight }
+    }
+
+    pub fn n_features(&self) -> usize {
+        self.center.len()
+    }
+
+    /// Columns past what the scaler was fitted on are left alone.
+    pub fn transform(&self, features: ArrayView2<f64>) -> Array2<f64> {
+        let mut scaled = features.to_owned();
+
+        for (mut column, (center, weight)) in scaled
+            .axis_iter_mut(Axis(1))
+            .zip(self.center.iter().zip(&self.weight))
+        {
+            column.mapv_inplace(|x| (x - center) * weight);
+        }
+
+        scaled
+    }
+}
+
+fn sorted(column: ArrayView1<f64>) -> Vec<f64> {
+    let mut sorted = column.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    sorted
+}
+
+/// Linear interpolation between the closest ranks.
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.;
+    }
+
+    let rank = q * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::array;
+
+    use super::*;
+
+    fn fit(kind: ScalerKind, features: &Array2<f64>) -> Array2<f64> {
+        Scaler::fit(kind, features.view(), &[]).transform(features.view())
+    }
+
+    #[test]
+    fn fixed_uses_the_weights() {
+        let features = array![[1., 2.], [3., 4.]];
+        let scaler = Scaler::fit(ScalerKind::Fixed, features.view(), &[10., 0.5]);
+
+        assert_eq!(
+            scaler.transform(features.view()),
+            array![[10., 1.], [30., 2.]]
+        );
+    }
+
+    #[test]
+    fn zscore_centers_and_scales() {
+        let scaled = fit(ScalerKind::ZScore, &array![[1.], [2.], [3.], [4.]]);
+        let std = 1.25f64.sqrt();
+
+        assert_eq!(
+            scaled.column(0).to_vec(),
+            [-1.5 / std, -0.5 / std, 0.5 / std, 1.5 / std]
+        );
+    }
+
+    #[test]
+    fn robust_ignores_the_outlier() {
+        // median 3, quartiles 2 and 4
+        let scaled = fit(ScalerKind::Robust, &array![[1.], [2.], [3.], [4.], [100.]]);
+
+        assert_eq!(scaled.column(0).to_vec(), [-1., -0.5, 0., 0.5, 48.5]);
+    }
+
+    #[test]
+    fn minmax_maps_onto_the_unit_range() {
+        let scaled = fit(ScalerKind::MinMax, &array![[2.], [4.], [6.]]);
+
+        assert_eq!(scaled.column(0).to_vec(), [0., 0.5, 1.]);
+    }
+
+    #[test]
+    fn constant_columns_stay_finite() {
+        let features = array![[5., 1.], [5., 2.], [5., 3.]];
+
+        for kind in [ScalerKind::ZScore, ScalerKind::Robust, ScalerKind::MinMax] {
+            let scaled = fit(kind, &features);
+
+            assert!(scaled.iter().all(|x| x.is_finite()), "{kind:?}");
+            assert_eq!(scaled.column(0).to_vec(), [0., 0., 0.], "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn columns_past_the_fit_pass_through() {
+        let scaler = Scaler::fit(ScalerKind::MinMax, array![[0.], [2.]].view(), &[]);
+        assert_eq!(scaler.n_features(), 1);
+
+        let scaled = scaler.transform(array![[1., 7.], [2., -3.]].view());
+        assert_eq!(scaled, array![[0.5, 7.], [1., -3.]]);
+    }
+}
@@ -1,62 +1,409 @@
 #![deny(clippy::all)]
 
+use std::fmt;
+use std::ops::RangeInclusive;
 use std::sync::LazyLock;
 
-use linfa_clustering::KMeans;
+use ndarray::Axis;
 use sonai_metrics::{
-    DistanceFunction, TextMetricFactory, TextMetrics, features_from_metrics, point_confidence,
+    Clusterer, Label, Model, TextMetricFactory, features_from_metrics_with, point_confidence,
 };
 
-const AI_CLUSTER: usize =
-    include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/model.ai.cluster"))[0] as usize;
+/// What [`Scorer`]s are handed, so they can be written without depending on
+/// sonai_metrics.
+pub use sonai_metrics::TextMetrics;
 
-static MODEL: LazyLock<KMeans<f64, DistanceFunction>> = LazyLock::new(|| {
+// keeps the README examples compiling
+#[doc = include_str!("../README.md")]
+#[cfg(doctest)]
+pub struct ReadmeDoctests;
+
+static MODEL: LazyLock<Model> = LazyLock::new(load_model);
+
+fn load_model() -> Model {
     let config = bincode::config::standard();
     bincode::serde::decode_from_slice(
-        include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/model.kmeans")),
+        include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/model.sonai")),
         config,
     )
     .unwrap()
     .0
+}
+
+static METRICS: LazyLock<Result<TextMetricFactory, Error>> = LazyLock::new(|| {
+    let mut factory = TextMetricFactory::new()
+        .unwrap()
+        .with_features(MODEL.features.clone());
+
+    if let Some(language_model) = &MODEL.language_model {
+        factory = factory.with_language_model(language_model.clone());
+    }
+
+    if MODEL.features.embeddings > 0 {
+        factory = with_embedder(factory, std::env::var("SONAI_EMBEDDER").ok(), &MODEL)?;
+    }
+
+    Ok(factory)
 });
 
-static METRICS: LazyLock<TextMetricFactory> = LazyLock::new(|| TextMetricFactory::new().unwrap());
+/// Why the model can't score anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The model uses embeddings but sonai was built without the
+    /// `embeddings` feature
+    EmbeddingsDisabled,
+    /// The model uses embeddings and `SONAI_EMBEDDER` isn't set
+    NoEmbedder,
+    /// The embedding model in `SONAI_EMBEDDER` didn't load
+    Embedder(String),
+    /// The model's embedding projection is missing or has the wrong width
+    Projection(String),
+    /// The model's columns don't match the registered metrics
+    Layout(String),
+    /// The embedding model failed on the text
+    Embedding(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmbeddingsDisabled => {
+                write!(
+                    f,
+                    "the model uses embeddings, enable the `embeddings` feature of sonai"
+                )
+            }
+            Self::NoEmbedder => write!(
+                f,
+                "the model uses embeddings, set SONAI_EMBEDDER to the embedding model directory"
+            ),
+            Self::Embedder(err) => write!(f, "couldn't load the embedding model: {err}"),
+            Self::Projection(err) => write!(f, "bad embedding projection: {err}"),
+            Self::Layout(err) => write!(f, "the model doesn't fit these metrics: {err}"),
+            Self::Embedding(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Loads the embedding model from `dir` for `model`'s projection.
+#[cfg(feature = "embeddings")]
+fn with_embedder(
+    factory: TextMetricFactory,
+    dir: Option<String>,
+    model: &Model,
+) -> Result<TextMetricFactory, Error> {
+    let dir = dir.ok_or(Error::NoEmbedder)?;
+    let embedder =
+        sonai_metrics::Embedder::load(dir).map_err(|err| Error::Embedder(err.to_string()))?;
+
+    let pca = model
+        .pca
+        .clone()
+        .ok_or_else(|| Error::Projection("the model has none".to_string()))?;
+    if pca.n_components() != model.features.embeddings {
+        return Err(Error::Projection(format!(
+            "{} components for {} embedding columns",
+            pca.n_components(),
+            model.features.embeddings
+        )));
+    }
+
+    Ok(factory.with_embedder(embedder, pca))
+}
+
+#[cfg(not(feature = "embeddings"))]
+fn with_embedder(
+    _: TextMetricFactory,
+    _: Option<String>,
+    _: &Model,
+) -> Result<TextMetricFactory, Error> {
+    Err(Error::EmbeddingsDisabled)
+}
 
 #[derive(Debug, serde::Serialize)]
 pub struct Prediction {
     pub chance_ai: f64,
     pub chance_human: f64,
     pub metrics: TextMetrics,
+    /// Stage whose score is reported in `chance_ai`
+    pub stage: Stage,
+    pub stages: Vec<StageScore>,
+    /// ln p(features), only for mixture models
+    pub log_likelihood: Option<f64>,
+    /// Less likely than 99% of the training set, don't trust the score much
+    pub out_of_distribution: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub enum Stage {
+    /// TextMetrics + KMeans
+    Heuristic,
+    /// The cascade's [`Scorer`]
+    Escalated,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StageScore {
+    pub stage: Stage,
+    pub chance_ai: f64,
+}
+
+/// Expensive second opinion, only consulted for texts the heuristics are
+/// unsure about.
+pub trait Scorer {
+    /// Chance the text is AI in percent, like [`Prediction::chance_ai`].
+    fn chance_ai(&self, devlog: &str, metrics: &TextMetrics) -> f64;
+}
+
+impl<F: Fn(&str, &TextMetrics) -> f64> Scorer for F {
+    fn chance_ai(&self, devlog: &str, metrics: &TextMetrics) -> f64 {
+        self(devlog, metrics)
+    }
+}
+
+/// Escalates to `scorer` when the heuristic `chance_ai` lands inside `uncertain`.
+pub struct Cascade<S> {
+    pub scorer: S,
+    pub uncertain: RangeInclusive<f64>,
+}
+
+impl<S: Scorer> Cascade<S> {
+    pub fn new(scorer: S, uncertain: RangeInclusive<f64>) -> Self {
+        Self { scorer, uncertain }
+    }
+
+    pub fn predict(&self, devlog: &str) -> Result<Prediction, Error> {
+        let mut prediction = _predict(devlog)?;
+
+        if !self.uncertain.contains(&prediction.chance_ai) {
+            return Ok(prediction);
+        }
+
+        let chance_ai = self
+            .scorer
+            .chance_ai(devlog, &prediction.metrics)
+            .clamp(0.0, 100.0);
+
+        prediction.stages.push(StageScore {
+            stage: Stage::Escalated,
+            chance_ai,
+        });
+
+        prediction.stage = Stage::Escalated;
+        prediction.chance_ai = chance_ai;
+        prediction.chance_human = 100.0 - chance_ai;
+
+        Ok(prediction)
+    }
 }
 
-fn _predict(devlog: &str) -> Prediction {
-    let sample = METRICS.calculate(devlog);
-    let features = features_from_metrics(&[&sample]);
+fn _predict(devlog: &str) -> Result<Prediction, Error> {
+    let metrics = METRICS.as_ref().map_err(Clone::clone)?;
+    let names: Vec<&str> = metrics.registry().names().collect();
+    let columns = MODEL
+        .columns(&names, &MODEL.features)
+        .map_err(Error::Layout)?;
+
+    let sample = metrics.calculate(devlog).map_err(Error::Embedding)?;
+    let features = features_from_metrics_with(&[&sample], &MODEL.features);
+    let features = MODEL
+        .scaler
+        .transform(features.select(Axis(1), &columns).view());
     let features = features.row(0);
-    let model = &*MODEL;
 
-    let (_, sims) = point_confidence(model, features);
+    let (sims, log_likelihood, out_of_distribution) = match &MODEL.clusterer {
+        Clusterer::KMeans { kmeans, distance } => (
+            point_confidence(kmeans.centroids().view(), distance, features).1,
+            None,
+            false,
+        ),
+        Clusterer::Seeded {
+            centroids,
+            distance,
+        } => (
+            point_confidence(centroids.view(), distance, features).1,
+            None,
+            false,
+        ),
+        Clusterer::Gmm(gmm) => {
+            let log_likelihood = gmm.log_likelihood(features);
+
+            (
+                gmm.posterior(features),
+                Some(log_likelihood),
+                log_likelihood < gmm.ood_threshold,
+            )
+        }
+    };
 
-    let chance_ai = sims.get(AI_CLUSTER).cloned().unwrap_or(0.0) * 100.0;
+    // several clusters can be AI, e.g. marketing copy and changelog lists
+    let chance_ai = sims
+        .iter()
+        .zip(&MODEL.labels)
+        .filter(|(_, label)| **label == Label::Ai)
+        .map(|(sim, _)| sim)
+        .sum::<f64>()
+        * 100.0;
     let chance_human = 100.0 - chance_ai;
 
-    Prediction {
+    Ok(Prediction {
         metrics: sample,
         chance_ai,
         chance_human,
-    }
+        stage: Stage::Heuristic,
+        stages: vec![StageScore {
+            stage: Stage::Heuristic,
+            chance_ai,
+        }],
+        log_likelihood,
+        out_of_distribution,
+    })
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-pub fn predict(devlog: &str) -> Prediction {
+pub fn predict(devlog: &str) -> Result<Prediction, Error> {
     _predict(devlog)
 }
 
+#[cfg(target_arch = "wasm32")]
+use serde::Serialize;
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
-pub fn predict(devlog: &str) -> JsValue {
-    serde_wasm_bindgen::to_value(&_predict(devlog)).unwrap()
+pub fn predict(devlog: &str) -> Result<JsValue, JsError> {
+    // metrics are a flattened map, keep them a plain object for JS
+    let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+    Ok(_predict(devlog)?.serialize(&serializer)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn predicts_with_the_bundled_model() {
+        let prediction = predict("I made a thing and it mostly works lol").unwrap();
+
+        assert!((0.0..=100.0).contains(&prediction.chance_ai));
+        assert!((prediction.chance_ai + prediction.chance_human - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cascade_escalates_only_uncertain_scores() {
+        let devlog = "I made a thing and it mostly works lol";
+        let heuristic = predict(devlog).unwrap().chance_ai;
+        let calls = std::cell::Cell::new(0);
+        let scorer = |_: &str, _: &TextMetrics| {
+            calls.set(calls.get() + 1);
+            42.0
+        };
+
+        let escalated = Cascade::new(&scorer, heuristic..=heuristic)
+            .predict(devlog)
+            .unwrap();
+        assert_eq!(escalated.stage, Stage::Escalated);
+        assert_eq!(escalated.chance_ai, 42.0);
+        assert_eq!(escalated.chance_human, 58.0);
+        assert_eq!(escalated.stages.len(), 2);
+        assert_eq!(escalated.stages[0].chance_ai, heuristic);
+        assert_eq!(calls.get(), 1);
+
+        let stopped = Cascade::new(&scorer, heuristic + 1.0..=heuristic + 2.0)
+            .predict(devlog)
+            .unwrap();
+        assert_eq!(stopped.stage, Stage::Heuristic);
+        assert_eq!(stopped.chance_ai, heuristic);
+        assert_eq!(stopped.stages.len(), 1);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn newer_metrics_dont_shift_the_bundled_model() {
+        let model = load_model();
+        let factory = TextMetricFactory::new().unwrap();
+        let mut names: Vec<&str> = factory.registry().names().collect();
+
+        assert_eq!(
+            model.columns(&names, &model.features),
+            Ok((0..model.metrics.len()).collect())
+        );
+
+        names.insert(0, "newer");
+        assert_eq!(
+            model.columns(&names, &model.features),
+            Ok((1..=model.metrics.len()).collect())
+        );
+
+        names.retain(|name| *name != "emoji");
+        assert!(model.columns(&names, &model.features).is_err());
+    }
+
+    #[cfg(feature = "embeddings")]
+    mod embeddings {
+        use super::*;
+        use ndarray::Array2;
+        use sonai_metrics::{FeatureSet, Pca};
+
+        const TINY_BERT: &str = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../sonai-metrics/testdata/tiny-bert"
+        );
+
+        fn model(components: usize, embeddings: usize) -> Model {
+            let data = Array2::from_shape_fn((10, 8), |(i, j)| ((i * 7 + j * 3) % 5) as f64);
+            let mut model = load_model();
+
+            model.pca = Some(Pca::fit(data.view(), components).unwrap());
+            model.features = FeatureSet {
+                embeddings,
+                ..model.features.clone()
+            };
+
+            model
+        }
+
+        fn factory(model: &Model) -> TextMetricFactory {
+            TextMetricFactory::new()
+                .unwrap()
+                .with_features(model.features.clone())
+        }
+
+        #[test]
+        fn embeds_with_the_tiny_model() {
+            let model = model(4, 4);
+            let factory =
+                with_embedder(factory(&model), Some(TINY_BERT.to_string()), &model).unwrap();
+
+            let metrics = factory.calculate("a short devlog.").unwrap();
+            assert_eq!(metrics.embedding.map(|embedding| embedding.len()), Some(4));
+        }
+
+        #[test]
+        fn missing_embedder_is_an_error() {
+            let model = model(4, 4);
+
+            assert_eq!(
+                with_embedder(factory(&model), None, &model).unwrap_err(),
+                Error::NoEmbedder
+            );
+            assert!(matches!(
+                with_embedder(factory(&model), Some("/nowhere".to_string()), &model),
+                Err(Error::Embedder(_))
+            ));
+        }
+
+        #[test]
+        fn narrow_projection_is_an_error() {
+            let model = model(2, 4);
+
+            assert!(matches!(
+                with_embedder(factory(&model), Some(TINY_BERT.to_string()), &model),
+                Err(Error::Projection(_))
+            ));
+        }
+    }
 }
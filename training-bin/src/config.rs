@@ -0,0 +1,357 @@
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+use sonai_metrics::{DistanceKind, FUNCTION_WORDS, FeatureSet, ScalerKind};
+
+use crate::KMeansParams;
+use crate::search::{Algorithm, Strategy};
+
+/// `training.toml`. Every key is optional, a missing file or key keeps the
+/// defaults, which match the committed model.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The file as read, recorded in the run manifest
+    #[serde(skip)]
+    pub source: String,
+    pub paths: Paths,
+    /// `[[sources]]`, Summer of Making alone when empty
+    pub sources: Vec<Source>,
+    pub fetch: Fetch,
+    pub features: Features,
+    pub train: Train,
+    pub evaluate: Evaluate,
+}
+
+impl Config {
+    pub async fn load(path: &Path) -> anyhow::Result<Self> {
+        if !tokio::fs::try_exists(path).await? {
+            return Ok(Self::default());
+        }
+
+        let text = tokio::fs::read_to_string(path).await?;
+
+        Self::parse(text).map_err(|err| anyhow::anyhow!("{}: {err}", path.display()))
+    }
+
+    pub fn parse(source: String) -> Result<Self, toml::de::Error> {
+        let mut config: Self = toml::from_str(&source)?;
+        config.source = source;
+
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Paths {
+    /// Fetched devlogs and projects, written by `fetch`
+    pub corpus: PathBuf,
+    /// Texts from before the corpus, only read by `migrate`
+    pub data: PathBuf,
+    /// Feature matrices, written by `metrics`
+    pub metrics: PathBuf,
+    /// Trained model, written by `train`
+    pub model: PathBuf,
+    /// Where `export` copies the trained model
+    pub export: PathBuf,
+    /// Demo page written by `report`
+    pub html: PathBuf,
+    /// Ranked search results
+    pub search: PathBuf,
+    /// What `train` ran on and produced, checked by `verify`
+    pub manifest: PathBuf,
+    /// Known-human texts for the language model, one per file
+    pub lm: Option<PathBuf>,
+    /// Seed texts in `ai/` and `human/`, one per file
+    pub seeds: Option<PathBuf>,
+    /// Local sentence embedding model, needs the `embeddings` feature
+    pub embeddings: Option<PathBuf>,
+}
+
+impl Default for Paths {
+    fn default() -> Self {
+        Self {
+            corpus: "corpus.db".into(),
+            data: "som.data".into(),
+            metrics: "metrics.bin".into(),
+            model: "model.sonai".into(),
+            export: "../sonai/model.sonai".into(),
+            html: "../inference-wasm-web/index.html".into(),
+            search: "search.tsv".into(),
+            manifest: "manifest.toml".into(),
+            lm: None,
+            seeds: None,
+            embeddings: None,
+        }
+    }
+}
+
+/// Where `fetch` reads texts from.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Source {
+    /// Summer of Making API, settings under `[fetch]`
+    Som,
+    /// Every .md, .markdown and .txt file under `path`
+    Dir(Dir),
+    /// One JSON object per line
+    Jsonl(Table),
+    /// Header row first
+    Csv(Table),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Dir {
+    /// Stored as `records.source`
+    pub name: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Table {
+    /// Stored as `records.source`
+    pub name: String,
+    pub path: PathBuf,
+    /// Field or column with the text
+    #[serde(default = "text_column")]
+    pub text: String,
+    /// Field or column identifying a row across syncs, the line or row number
+    /// when unset
+    pub id: Option<String>,
+}
+
+fn text_column() -> String {
+    "text".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Fetch {
+    /// Summer of Making API base, point it at a local mock to test syncing
+    pub api: String,
+    /// Pages in flight at once
+    pub concurrency: usize,
+    /// Tries per page before the sync fails
+    pub attempts: usize,
+    /// Per request, including reading the body
+    pub timeout_secs: u64,
+    /// Across all pages in flight, unlimited when unset
+    pub requests_per_second: Option<f64>,
+    /// Save every response under this directory
+    pub record: Option<PathBuf>,
+    /// Serve the responses saved by `record` instead of calling the API
+    pub replay: Option<PathBuf>,
+    /// Replace ids, names, bios, links and mentions in recorded responses
+    pub anonymise: bool,
+    /// Score project titles as texts of their own. Off, `fetch --full` deletes
+    /// the stored ones, as it does for comments and users
+    pub titles: bool,
+    /// Also sync `/comments`
+    pub comments: bool,
+    /// Also sync `/users`, stored but not scored
+    pub users: bool,
+}
+
+impl Default for Fetch {
+    fn default() -> Self {
+        Self {
+            api: "https://summer.hackclub.com/api/v1".to_string(),
+            concurrency: 20,
+            attempts: 3,
+            timeout_secs: 30,
+            requests_per_second: None,
+            record: None,
+            replay: None,
+            anonymise: true,
+            titles: false,
+            comments: false,
+            users: false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Features {
+    pub rhythm: bool,
+    pub lexical: bool,
+    pub function_words: bool,
+    pub punctuation: bool,
+    /// PCA width of the embedding block, used when `paths.embeddings` is set
+    pub embedding_dims: usize,
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Self {
+            rhythm: false,
+            lexical: false,
+            function_words: false,
+            punctuation: false,
+            embedding_dims: 16,
+        }
+    }
+}
+
+impl Features {
+    pub fn feature_set(&self, perplexity: bool, embeddings: bool) -> FeatureSet {
+        FeatureSet {
+            rhythm: self.rhythm,
+            lexical: self.lexical,
+            function_words: self
+                .function_words
+                .then(|| FUNCTION_WORDS.iter().map(ToString::to_string).collect()),
+            punctuation: self.punctuation,
+            perplexity,
+            embeddings: if embeddings { self.embedding_dims } else { 0 },
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Train {
+    /// kmeans, gmm, gmm-full, gmm-diagonal or gmm-spherical
+    #[serde(deserialize_with = "parsed")]
+    pub algorithm: Algorithm,
+    pub k: ClusterCount,
+    /// linf, l1, l2, cosine or mahalanobis
+    #[serde(deserialize_with = "parsed")]
+    pub distance: DistanceKind,
+    /// fixed, zscore, robust or minmax
+    #[serde(deserialize_with = "parsed")]
+    pub scaler: ScalerKind,
+    pub seed: u64,
+    pub max_iterations: u64,
+    pub runs: usize,
+    /// Pin the seeds to clusters of their label, KMeans only
+    pub semi_supervised: bool,
+    /// grid, random or random:N, replaces the settings above with the best
+    /// candidate
+    #[serde(deserialize_with = "parsed_opt")]
+    pub search: Option<Strategy>,
+    /// Cross-validation folds, at least 2 so every fold has training rows
+    #[serde(deserialize_with = "folds")]
+    pub folds: usize,
+}
+
+impl Default for Train {
+    fn default() -> Self {
+        let params = KMeansParams::default();
+
+        Self {
+            algorithm: Algorithm::KMeans,
+            k: ClusterCount::Fixed(2),
+            distance: DistanceKind::default(),
+            scaler: ScalerKind::default(),
+            seed: params.seed,
+            max_iterations: params.max_iterations,
+            runs: params.runs,
+            semi_supervised: false,
+            search: None,
+            folds: 5,
+        }
+    }
+}
+
+impl Train {
+    pub fn kmeans_params(&self) -> KMeansParams {
+        KMeansParams {
+            seed: self.seed,
+            max_iterations: self.max_iterations,
+            runs: self.runs,
+        }
+    }
+}
+
+/// `k = 3` or `k = "auto"`, auto picks the best silhouette in 2..=8.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+pub enum ClusterCount {
+    Fixed(usize),
+    Auto(Auto),
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Auto {
+    Auto,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Evaluate {
+    /// Refit with every distance and print how each splits the corpus
+    pub compare_distances: bool,
+}
+
+fn parsed<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(D::Error::custom)
+}
+
+fn folds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    let folds = usize::deserialize(deserializer)?;
+    if folds < 2 {
+        return Err(D::Error::custom(format!(
+            "folds = {folds}, cross-validation needs at least 2"
+        )));
+    }
+
+    Ok(folds)
+}
+
+fn parsed_opt<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    parsed(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_need_two() {
+        assert_eq!(Config::parse(String::new()).unwrap().train.folds, 5);
+        assert_eq!(
+            Config::parse("[train]\nfolds = 2".into())
+                .unwrap()
+                .train
+                .folds,
+            2
+        );
+
+        let err = Config::parse("[train]\nfolds = 1".into()).unwrap_err();
+        assert!(err.to_string().contains("at least 2"), "{err}");
+    }
+
+    #[test]
+    fn tables_reject_unknown_fields() {
+        let source = "[[sources]]\ntype = \"csv\"\nname = \"essays\"\npath = \"essays.csv\"\n";
+        assert_eq!(Config::parse(source.into()).unwrap().sources.len(), 1);
+
+        let err = Config::parse(format!("{source}txt = \"body\"")).unwrap_err();
+        assert!(err.to_string().contains("unknown field `txt`"), "{err}");
+        let source = "[[sources]]\ntype = \"dir\"\nname = \"notes\"\npath = \"notes\"\n";
+        assert_eq!(Config::parse(source.into()).unwrap().sources.len(), 1);
+
+        let err = Config::parse(format!("{source}paht = \"other\"")).unwrap_err();
+        assert!(err.to_string().contains("unknown field `paht`"), "{err}");
+    }
+}
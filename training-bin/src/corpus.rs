@@ -0,0 +1,772 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rusqlite::{Connection, OptionalExtension, params, params_from_iter};
+
+/// Schema changes in order, `PRAGMA user_version` counts how many ran.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE records (
+    kind       TEXT,
+    id         INTEGER,
+    project_id INTEGER,
+    slack_id   TEXT,
+    text       TEXT NOT NULL,
+    created_at TEXT,
+    updated_at TEXT,
+    UNIQUE (kind, id)
+);",
+    // incremental sync, `sweep` is the last sync that saw the record
+    "ALTER TABLE records ADD COLUMN sweep INTEGER;
+CREATE TABLE sync (
+    kind        TEXT PRIMARY KEY,
+    sweep       INTEGER NOT NULL,
+    full        INTEGER NOT NULL,
+    in_progress INTEGER NOT NULL,
+    pages       INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE sync_pages (
+    kind TEXT NOT NULL,
+    page INTEGER NOT NULL,
+    PRIMARY KEY (kind, page)
+);",
+    // other data sources, `key` identifies a document within its source
+    "ALTER TABLE records ADD COLUMN source TEXT NOT NULL DEFAULT 'som';
+ALTER TABLE records ADD COLUMN key TEXT;
+CREATE UNIQUE INDEX records_source_key ON records (source, key);",
+    // every devlog and project field, comments and users
+    "ALTER TABLE records ADD COLUMN devlog_id INTEGER;
+ALTER TABLE records ADD COLUMN title TEXT;
+ALTER TABLE records ADD COLUMN category TEXT;
+ALTER TABLE records ADD COLUMN readme_link TEXT;
+ALTER TABLE records ADD COLUMN demo_link TEXT;
+ALTER TABLE records ADD COLUMN repo_link TEXT;
+ALTER TABLE records ADD COLUMN attachment TEXT;
+-- so the next sync rewrites them with the new fields
+UPDATE records SET updated_at = NULL WHERE kind IN ('devlog', 'project');",
+    // migrated texts get a source of their own, no sync owns them
+    "UPDATE records SET source = 'migrated' WHERE kind IS NULL AND key IS NULL;",
+];
+
+/// Source of the texts `migrate` imported from the old `som.data`.
+pub const MIGRATED: &str = "migrated";
+
+/// Record columns in [`Record`] order, everything but the sync bookkeeping.
+const FIELDS: [&str; 16] = [
+    "source",
+    "key",
+    "kind",
+    "id",
+    "project_id",
+    "devlog_id",
+    "slack_id",
+    "title",
+    "category",
+    "readme_link",
+    "demo_link",
+    "repo_link",
+    "attachment",
+    "text",
+    "created_at",
+    "updated_at",
+];
+
+// users are kept for joins, they have no text to score
+const SCORED: &str = "kind IS NOT 'user'";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Devlog,
+    /// Project description
+    Project,
+    /// Project title, only with `fetch.titles`
+    Title,
+    Comment,
+    /// Author profile, `text` is the bio
+    User,
+    /// A file, or a row of a JSONL or CSV file
+    Document,
+}
+
+impl ToSql for Kind {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(match self {
+            Self::Devlog => "devlog",
+            Self::Project => "project",
+            Self::Title => "title",
+            Self::Comment => "comment",
+            Self::User => "user",
+            Self::Document => "document",
+        }
+        .into())
+    }
+}
+
+impl FromSql for Kind {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value.as_str()? {
+            "devlog" => Ok(Self::Devlog),
+            "project" => Ok(Self::Project),
+            "title" => Ok(Self::Title),
+            "comment" => Ok(Self::Comment),
+            "user" => Ok(Self::User),
+            "document" => Ok(Self::Document),
+            other => Err(FromSqlError::Other(
+                format!("unknown record kind `{other}`").into(),
+            )),
+        }
+    }
+}
+
+/// One text of the corpus with where it came from. Texts migrated from the
+/// old `som.data` only have `text`, under the [`MIGRATED`] source.
+#[derive(Debug, Clone, Default)]
+pub struct Record {
+    /// Name of the data source, `som` for Summer of Making
+    pub source: String,
+    /// File path, id column or line of a document, SoM records go by `kind`
+    /// and `id`
+    pub key: Option<String>,
+    pub kind: Option<Kind>,
+    /// Id in the API, of the project for titles
+    pub id: Option<u32>,
+    /// Project the devlog belongs to, the project itself for descriptions
+    /// and titles
+    pub project_id: Option<u32>,
+    /// Devlog a comment is on
+    pub devlog_id: Option<u32>,
+    pub slack_id: Option<String>,
+    /// Project title, or a user's display name
+    pub title: Option<String>,
+    pub category: Option<String>,
+    pub readme_link: Option<String>,
+    pub demo_link: Option<String>,
+    pub repo_link: Option<String>,
+    /// Devlog attachment as JSON
+    pub attachment: Option<String>,
+    pub text: String,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+impl Record {
+    fn values(&self) -> [&dyn ToSql; FIELDS.len()] {
+        [
+            &self.source,
+            &self.key,
+            &self.kind,
+            &self.id,
+            &self.project_id,
+            &self.devlog_id,
+            &self.slack_id,
+            &self.title,
+            &self.category,
+            &self.readme_link,
+            &self.demo_link,
+            &self.repo_link,
+            &self.attachment,
+            &self.text,
+            &self.created_at,
+            &self.updated_at,
+        ]
+    }
+
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            source: row.get(0)?,
+            key: row.get(1)?,
+            kind: row.get(2)?,
+            id: row.get(3)?,
+            project_id: row.get(4)?,
+            devlog_id: row.get(5)?,
+            slack_id: row.get(6)?,
+            title: row.get(7)?,
+            category: row.get(8)?,
+            readme_link: row.get(9)?,
+            demo_link: row.get(10)?,
+            repo_link: row.get(11)?,
+            attachment: row.get(12)?,
+            text: row.get(13)?,
+            created_at: row.get(14)?,
+            updated_at: row.get(15)?,
+        })
+    }
+
+    /// Where the text came from, for printing.
+    pub fn origin(&self) -> String {
+        match (self.kind, self.id, self.project_id) {
+            (Some(Kind::Devlog), Some(id), Some(project_id)) => {
+                format!("devlog {id}, project {project_id}")
+            }
+            (Some(Kind::Devlog), Some(id), None) => format!("devlog {id}"),
+            (Some(Kind::Project), Some(id), _) => format!("project {id}"),
+            (Some(Kind::Title), Some(id), _) => format!("title of project {id}"),
+            (Some(Kind::Comment), Some(id), _) => match self.devlog_id {
+                Some(devlog_id) => format!("comment {id}, devlog {devlog_id}"),
+                None => format!("comment {id}"),
+            },
+            (Some(Kind::User), Some(id), _) => format!("user {id}"),
+            (Some(Kind::Document), ..) => {
+                format!("{} {}", self.source, self.key.as_deref().unwrap_or("?"))
+            }
+            _ => MIGRATED.to_string(),
+        }
+    }
+}
+
+/// One pass over an endpoint, saved so an interrupted sync picks up where it
+/// stopped.
+#[derive(Debug)]
+pub struct Sweep {
+    pub id: i64,
+    /// Every page, the only way to notice deletions
+    pub full: bool,
+    pub resumed: bool,
+    /// Page count when the last sweep finished, 0 before the first
+    pub last_pages: u32,
+    /// Pages already stored by this sweep
+    pub done: HashSet<u32>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Changes {
+    pub added: usize,
+    pub updated: usize,
+    pub deleted: usize,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.added + self.updated + self.deleted == 0
+    }
+}
+
+impl std::ops::AddAssign for Changes {
+    fn add_assign(&mut self, other: Self) {
+        self.added += other.added;
+        self.updated += other.updated;
+        self.deleted += other.deleted;
+    }
+}
+
+/// SQLite file holding every record. Feature rows follow the insertion
+/// order, so row i of `metrics` is the i-th record.
+pub struct Corpus {
+    conn: Connection,
+}
+
+impl Corpus {
+    /// Creates the file if needed and brings the schema up to date.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let mut conn = Connection::open(path)?;
+
+        let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        let tx = conn.transaction()?;
+        for migration in MIGRATIONS.iter().skip(version) {
+            tx.execute_batch(migration)?;
+        }
+        tx.pragma_update(None, "user_version", MIGRATIONS.len())?;
+        tx.commit()?;
+
+        Ok(Self { conn })
+    }
+
+    pub fn len(&self) -> anyhow::Result<usize> {
+        Ok(self
+            .conn
+            .query_row("SELECT COUNT(*) FROM records", [], |row| row.get(0))?)
+    }
+
+    /// Resumes the unfinished sweep of `kind` or starts the next one. The
+    /// first sweep is always full.
+    pub fn start_sweep(&mut self, kind: Kind, full: bool) -> anyhow::Result<Sweep> {
+        let tx = self.conn.transaction()?;
+
+        let state: Option<(i64, bool, bool, u32)> = tx
+            .query_row(
+                "SELECT sweep, full, in_progress, pages FROM sync WHERE kind = ?1",
+                [kind],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional()?;
+
+        let sweep = match state {
+            Some((id, was_full, true, last_pages)) => {
+                let mut select = tx.prepare("SELECT page FROM sync_pages WHERE kind = ?1")?;
+                let done = select
+                    .query_map([kind], |row| row.get(0))?
+                    .collect::<Result<_, _>>()?;
+
+                Sweep {
+                    id,
+                    full: was_full || full,
+                    resumed: true,
+                    last_pages,
+                    done,
+                }
+            }
+            Some((id, _, false, last_pages)) => Sweep {
+                id: id + 1,
+                full,
+                resumed: false,
+                last_pages,
+                done: HashSet::new(),
+            },
+            None => Sweep {
+                id: 1,
+                full: true,
+                resumed: false,
+                last_pages: 0,
+                done: HashSet::new(),
+            },
+        };
+
+        tx.execute(
+            "INSERT INTO sync (kind, sweep, full, in_progress, pages) VALUES (?1, ?2, ?3, 1, ?4)
+             ON CONFLICT (kind) DO UPDATE SET
+                 sweep = excluded.sweep,
+                 full = excluded.full,
+                 in_progress = 1",
+            params![kind, sweep.id, sweep.full, sweep.last_pages],
+        )?;
+        tx.commit()?;
+
+        Ok(sweep)
+    }
+
+    /// Stores one fetched page and marks it done, in one transaction. A
+    /// record counts as updated when its `updated_at` (or text, without
+    /// timestamps) changed.
+    pub fn apply_page(
+        &mut self,
+        kind: Kind,
+        sweep: &Sweep,
+        page: u32,
+        records: &[Record],
+    ) -> anyhow::Result<Changes> {
+        let mut changes = Changes::default();
+        let tx = self.conn.transaction()?;
+
+        {
+            let mut existing = tx.prepare(
+                "SELECT rowid, text, updated_at FROM records WHERE kind = ?1 AND id = ?2",
+            )?;
+            let mut insert = tx.prepare(&insert_sql())?;
+            let mut update = tx.prepare(&update_sql())?;
+            let mut seen = tx.prepare("UPDATE records SET sweep = ?2 WHERE rowid = ?1")?;
+
+            for record in records {
+                let row: Option<(i64, String, Option<String>)> = existing
+                    .query_row(params![record.kind, record.id], |row| {
+                        Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                    })
+                    .optional()?;
+
+                match row {
+                    None => {
+                        insert.execute(params_from_iter(
+                            record.values().into_iter().chain([&sweep.id as &dyn ToSql]),
+                        ))?;
+                        changes.added += 1;
+                    }
+                    Some((rowid, text, updated_at))
+                        if updated_at != record.updated_at
+                            || (updated_at.is_none() && text != record.text) =>
+                    {
+                        update.execute(params_from_iter(
+                            record
+                                .values()
+                                .into_iter()
+                                .chain([&sweep.id as &dyn ToSql, &rowid]),
+                        ))?;
+                        changes.updated += 1;
+                    }
+                    Some((rowid, ..)) => {
+                        seen.execute(params![rowid, sweep.id])?;
+                    }
+                }
+            }
+        }
+
+        tx.execute(
+            "INSERT OR IGNORE INTO sync_pages (kind, page) VALUES (?1, ?2)",
+            params![kind, page],
+        )?;
+        tx.commit()?;
+
+        Ok(changes)
+    }
+
+    /// Closes the sweep. A full one deletes every record of `kinds` it didn't
+    /// see, returning how many.
+    pub fn finish_sweep(
+        &mut self,
+        kind: Kind,
+        kinds: &[Kind],
+        sweep: &Sweep,
+        pages: u32,
+    ) -> anyhow::Result<usize> {
+        let tx = self.conn.transaction()?;
+        let mut deleted = 0;
+
+        if sweep.full {
+            for kind in kinds {
+                deleted += tx.execute(
+                    "DELETE FROM records WHERE kind = ?1 AND (sweep IS NULL OR sweep < ?2)",
+                    params![kind, sweep.id],
+                )?;
+            }
+        }
+
+        tx.execute("DELETE FROM sync_pages WHERE kind = ?1", [kind])?;
+        tx.execute(
+            "UPDATE sync SET in_progress = 0, pages = ?2 WHERE kind = ?1",
+            params![kind, pages],
+        )?;
+        tx.commit()?;
+
+        Ok(deleted)
+    }
+
+    /// Deletes every record of `kind` and its sync state, for endpoints that
+    /// were turned off. Syncing them again starts with a full sweep.
+    pub fn drop_kind(&mut self, kind: Kind) -> anyhow::Result<usize> {
+        let tx = self.conn.transaction()?;
+        let deleted = tx.execute("DELETE FROM records WHERE kind = ?1", [kind])?;
+        tx.execute("DELETE FROM sync_pages WHERE kind = ?1", [kind])?;
+        tx.execute("DELETE FROM sync WHERE kind = ?1", [kind])?;
+        tx.commit()?;
+
+        Ok(deleted)
+    }
+
+    /// Makes the records of `source` exactly `records`, matched by key. For
+    /// sources small enough to read whole every time.
+    pub fn replace_source(&mut self, source: &str, records: &[Record]) -> anyhow::Result<Changes> {
+        let mut changes = Changes::default();
+        let tx = self.conn.transaction()?;
+
+        {
+            let mut existing: HashMap<String, (i64, String, Option<String>)> = tx
+                .prepare(
+                    "SELECT key, rowid, text, updated_at FROM records
+                     WHERE source = ?1 AND key IS NOT NULL",
+                )?
+                .query_map([source], |row| {
+                    Ok((row.get(0)?, (row.get(1)?, row.get(2)?, row.get(3)?)))
+                })?
+                .collect::<Result<_, _>>()?;
+
+            let mut insert = tx.prepare(&insert_sql())?;
+            let mut update = tx.prepare(&update_sql())?;
+
+            for record in records {
+                let key = record.key.clone().unwrap_or_default();
+
+                match existing.remove(&key) {
+                    None => {
+                        insert.execute(params_from_iter(
+                            record
+                                .values()
+                                .into_iter()
+                                .chain([&None::<i64> as &dyn ToSql]),
+                        ))?;
+                        changes.added += 1;
+                    }
+                    Some((rowid, text, updated_at))
+                        if text != record.text || updated_at != record.updated_at =>
+                    {
+                        update.execute(params_from_iter(
+                            record
+                                .values()
+                                .into_iter()
+                                .chain([&None::<i64> as &dyn ToSql, &rowid]),
+                        ))?;
+                        changes.updated += 1;
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            let mut delete = tx.prepare("DELETE FROM records WHERE rowid = ?1")?;
+            for (rowid, ..) in existing.into_values() {
+                delete.execute([rowid])?;
+                changes.deleted += 1;
+            }
+        }
+
+        tx.commit()?;
+
+        Ok(changes)
+    }
+
+    /// The old bincode `Vec<String>`, only into an empty corpus.
+    pub fn import_legacy(&mut self, texts: Vec<String>) -> anyhow::Result<usize> {
+        let existing = self.len()?;
+        if existing > 0 {
+            anyhow::bail!("the corpus already holds {existing} records");
+        }
+
+        let tx = self.conn.transaction()?;
+        {
+            let mut insert = tx.prepare("INSERT INTO records (source, text) VALUES (?1, ?2)")?;
+            for text in &texts {
+                insert.execute([MIGRATED, text])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(texts.len())
+    }
+
+    /// Everything scored, in feature row order.
+    pub fn records(&self) -> anyhow::Result<Vec<Record>> {
+        let mut select = self.conn.prepare(&format!(
+            "SELECT {} FROM records WHERE {SCORED} ORDER BY rowid",
+            FIELDS.join(", ")
+        ))?;
+
+        let records = select
+            .query_map([], Record::from_row)?
+            .collect::<Result<_, _>>()?;
+
+        Ok(records)
+    }
+
+    pub fn texts(&self) -> anyhow::Result<Vec<String>> {
+        let mut select = self.conn.prepare(&format!(
+            "SELECT text FROM records WHERE {SCORED} ORDER BY rowid"
+        ))?;
+
+        let texts = select
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+
+        Ok(texts)
+    }
+}
+
+// both take the record values, then `sweep`
+fn insert_sql() -> String {
+    format!(
+        "INSERT INTO records ({}, sweep) VALUES ({})",
+        FIELDS.join(", "),
+        (1..=FIELDS.len() + 1)
+            .map(|i| format!("?{i}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+// and `rowid` last
+fn update_sql() -> String {
+    format!(
+        "UPDATE records SET {}, sweep = ?{} WHERE rowid = ?{}",
+        FIELDS
+            .iter()
+            .enumerate()
+            .map(|(i, field)| format!("{field} = ?{}", i + 1))
+            .collect::<Vec<_>>()
+            .join(", "),
+        FIELDS.len() + 1,
+        FIELDS.len() + 2,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corpus() -> Corpus {
+        Corpus::open(Path::new(":memory:")).unwrap()
+    }
+
+    fn devlog(id: u32, updated_at: &str) -> Record {
+        Record {
+            source: "som".into(),
+            kind: Some(Kind::Devlog),
+            id: Some(id),
+            text: format!("devlog {id}"),
+            updated_at: Some(updated_at.into()),
+            ..Default::default()
+        }
+    }
+
+    fn ids(corpus: &Corpus) -> Vec<u32> {
+        let mut ids: Vec<u32> = corpus
+            .records()
+            .unwrap()
+            .into_iter()
+            .filter_map(|record| record.id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn first_sweep_is_full() {
+        let mut corpus = corpus();
+
+        let sweep = corpus.start_sweep(Kind::Devlog, false).unwrap();
+        assert!(sweep.full && !sweep.resumed);
+        assert_eq!((sweep.id, sweep.last_pages), (1, 0));
+
+        let changes = corpus
+            .apply_page(Kind::Devlog, &sweep, 1, &[devlog(1, "a"), devlog(2, "a")])
+            .unwrap();
+        assert_eq!(changes.added, 2);
+        assert_eq!(
+            corpus
+                .finish_sweep(Kind::Devlog, &[Kind::Devlog], &sweep, 1)
+                .unwrap(),
+            0
+        );
+
+        let next = corpus.start_sweep(Kind::Devlog, false).unwrap();
+        assert!(!next.full && !next.resumed);
+        assert_eq!((next.id, next.last_pages), (2, 1));
+    }
+
+    #[test]
+    fn updates_go_by_timestamp() {
+        let mut corpus = corpus();
+        let sweep = corpus.start_sweep(Kind::Devlog, false).unwrap();
+        corpus
+            .apply_page(Kind::Devlog, &sweep, 1, &[devlog(1, "a"), devlog(2, "a")])
+            .unwrap();
+        corpus
+            .finish_sweep(Kind::Devlog, &[Kind::Devlog], &sweep, 1)
+            .unwrap();
+
+        let sweep = corpus.start_sweep(Kind::Devlog, false).unwrap();
+        let changes = corpus
+            .apply_page(Kind::Devlog, &sweep, 1, &[devlog(1, "a"), devlog(2, "b")])
+            .unwrap();
+
+        assert_eq!((changes.added, changes.updated), (0, 1));
+    }
+
+    #[test]
+    fn interrupted_sweeps_resume() {
+        let mut corpus = corpus();
+        let sweep = corpus.start_sweep(Kind::Devlog, false).unwrap();
+        corpus
+            .apply_page(Kind::Devlog, &sweep, 1, &[devlog(1, "a")])
+            .unwrap();
+        corpus
+            .apply_page(Kind::Devlog, &sweep, 2, &[devlog(2, "a")])
+            .unwrap();
+
+        // never finished
+        let resumed = corpus.start_sweep(Kind::Devlog, false).unwrap();
+        assert!(resumed.resumed && resumed.full);
+        assert_eq!(resumed.id, sweep.id);
+        assert_eq!(resumed.done, HashSet::from([1, 2]));
+
+        // the pages stored before the interruption count as seen
+        corpus
+            .apply_page(Kind::Devlog, &resumed, 3, &[devlog(3, "a")])
+            .unwrap();
+        let deleted = corpus
+            .finish_sweep(Kind::Devlog, &[Kind::Devlog], &resumed, 3)
+            .unwrap();
+        assert_eq!(deleted, 0);
+        assert_eq!(ids(&corpus), [1, 2, 3]);
+
+        let next = corpus.start_sweep(Kind::Devlog, false).unwrap();
+        assert!(!next.resumed && next.done.is_empty());
+    }
+
+    #[test]
+    fn only_full_sweeps_delete() {
+        let mut corpus = corpus();
+        let sweep = corpus.start_sweep(Kind::Devlog, false).unwrap();
+        corpus
+            .apply_page(Kind::Devlog, &sweep, 1, &[devlog(1, "a"), devlog(2, "a")])
+            .unwrap();
+        corpus
+            .finish_sweep(Kind::Devlog, &[Kind::Devlog], &sweep, 1)
+            .unwrap();
+
+        let sweep = corpus.start_sweep(Kind::Devlog, false).unwrap();
+        corpus
+            .apply_page(Kind::Devlog, &sweep, 1, &[devlog(1, "a")])
+            .unwrap();
+        assert_eq!(
+            corpus
+                .finish_sweep(Kind::Devlog, &[Kind::Devlog], &sweep, 1)
+                .unwrap(),
+            0
+        );
+        assert_eq!(ids(&corpus), [1, 2]);
+
+        let sweep = corpus.start_sweep(Kind::Devlog, true).unwrap();
+        corpus
+            .apply_page(Kind::Devlog, &sweep, 1, &[devlog(1, "a")])
+            .unwrap();
+        assert_eq!(
+            corpus
+                .finish_sweep(Kind::Devlog, &[Kind::Devlog], &sweep, 1)
+                .unwrap(),
+            1
+        );
+        assert_eq!(ids(&corpus), [1]);
+    }
+
+    #[test]
+    fn sweeps_delete_every_kind_they_own() {
+        let mut corpus = corpus();
+        let project = |kind| Record {
+            kind: Some(kind),
+            ..devlog(1, "a")
+        };
+
+        let sweep = corpus.start_sweep(Kind::Project, false).unwrap();
+        corpus
+            .apply_page(
+                Kind::Project,
+                &sweep,
+                1,
+                &[project(Kind::Project), project(Kind::Title)],
+            )
+            .unwrap();
+        corpus
+            .finish_sweep(Kind::Project, &[Kind::Project, Kind::Title], &sweep, 1)
+            .unwrap();
+
+        // titles turned off
+        let sweep = corpus.start_sweep(Kind::Project, true).unwrap();
+        corpus
+            .apply_page(Kind::Project, &sweep, 1, &[project(Kind::Project)])
+            .unwrap();
+        let deleted = corpus
+            .finish_sweep(Kind::Project, &[Kind::Project, Kind::Title], &sweep, 1)
+            .unwrap();
+
+        assert_eq!(deleted, 1);
+        assert_eq!(corpus.len().unwrap(), 1);
+    }
+
+    #[test]
+    fn dropped_kinds_start_over() {
+        let mut corpus = corpus();
+        let sweep = corpus.start_sweep(Kind::Comment, false).unwrap();
+        corpus
+            .apply_page(
+                Kind::Comment,
+                &sweep,
+                1,
+                &[Record {
+                    kind: Some(Kind::Comment),
+                    ..devlog(1, "a")
+                }],
+            )
+            .unwrap();
+        corpus
+            .finish_sweep(Kind::Comment, &[Kind::Comment], &sweep, 1)
+            .unwrap();
+
+        assert_eq!(corpus.drop_kind(Kind::Comment).unwrap(), 1);
+        assert!(corpus.start_sweep(Kind::Comment, false).unwrap().full);
+    }
+}
@@ -0,0 +1,83 @@
+use std::path::Path;
+
+use sonai_metrics::{Pca, TextMetrics};
+
+/// Embeds every text, fits the projection down to `dims` and stores the
+/// reduced embedding on each metric.
+#[cfg(feature = "embeddings")]
+pub fn embed_corpus(
+    model_dir: &Path,
+    texts: &[String],
+    metrics: &mut [TextMetrics],
+    dims: usize,
+) -> anyhow::Result<Pca> {
+    let embeddings = embed(model_dir, texts)?;
+
+    let pca = Pca::fit(embeddings.view(), dims).map_err(anyhow::Error::msg)?;
+    store(&pca, embeddings, metrics);
+
+    Ok(pca)
+}
+
+/// Same as [`embed_corpus`] but projects with an already fitted `pca`.
+#[cfg(feature = "embeddings")]
+pub fn embed_with(
+    model_dir: &Path,
+    texts: &[String],
+    metrics: &mut [TextMetrics],
+    pca: &Pca,
+) -> anyhow::Result<()> {
+    let embeddings = embed(model_dir, texts)?;
+    store(pca, embeddings, metrics);
+
+    Ok(())
+}
+
+#[cfg(feature = "embeddings")]
+fn embed(model_dir: &Path, texts: &[String]) -> anyhow::Result<ndarray::Array2<f64>> {
+    use ndarray::{Axis, concatenate};
+    use sonai_metrics::Embedder;
+
+    let embedder = Embedder::load(model_dir)?;
+
+    let mut batches = Vec::new();
+    for (i, chunk) in texts.chunks(64).enumerate() {
+        let chunk: Vec<&str> = chunk.iter().map(String::as_str).collect();
+        batches.push(embedder.embed(&chunk)?);
+
+        println!("Embedded {}/{}", (i * 64 + chunk.len()), texts.len());
+    }
+
+    let views: Vec<_> = batches.iter().map(|batch| batch.view()).collect();
+
+    Ok(concatenate(Axis(0), &views).expect("batches share the embedding width"))
+}
+
+#[cfg(feature = "embeddings")]
+fn store(pca: &Pca, embeddings: ndarray::Array2<f64>, metrics: &mut [TextMetrics]) {
+    let reduced = pca.transform(embeddings.view());
+
+    for (metric, row) in metrics.iter_mut().zip(reduced.rows()) {
+        metric.embedding = Some(row.to_vec());
+    }
+}
+
+#[cfg(not(feature = "embeddings"))]
+pub fn embed_corpus(
+    _model_dir: &Path,
+    _texts: &[String],
+    _metrics: &mut [TextMetrics],
+    _dims: usize,
+) -> anyhow::Result<Pca> {
+    anyhow::bail!("training-bin was built without the `embeddings` feature")
+}
+
+#[cfg(not(feature = "embeddings"))]
+pub fn embed_with(
+    _model_dir: &Path,
+    _texts: &[String],
+    _metrics: &mut [TextMetrics],
+    _pca: &Pca,
+) -> anyhow::Result<()> {
+    anyhow::bail!("training-bin was built without the `embeddings` feature")
+}
@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+use crate::http::Raw;
+
+/// Saved API responses, `DIR/<endpoint>/page-N.json` holds the body of a good
+/// response and `page-N.fail` one `<status> [retry-after secs]` line per
+/// failed attempt before it.
+pub struct Fixtures {
+    dir: PathBuf,
+}
+
+impl Fixtures {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    fn path(&self, url: &str, page: u32, ext: &str) -> PathBuf {
+        let endpoint = url.trim_end_matches('/').rsplit('/').next().unwrap_or(url);
+
+        self.dir.join(endpoint).join(format!("page-{page}.{ext}"))
+    }
+}
+
+/// Writes every response it's handed as fixtures.
+pub struct Recorder {
+    fixtures: Fixtures,
+    /// Shared by every page of the recording, `None` keeps responses as sent
+    pseudonyms: Option<Mutex<Pseudonyms>>,
+}
+
+impl Recorder {
+    pub fn new(dir: &Path, anonymise: bool) -> Self {
+        Self {
+            fixtures: Fixtures::new(dir),
+            pseudonyms: anonymise.then(Mutex::default),
+        }
+    }
+
+    pub async fn save(&self, url: &str, page: u32, attempt: usize, raw: &Raw) -> io::Result<()> {
+        let fail = self.fixtures.path(url, page, "fail");
+        if let Some(dir) = fail.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+
+        // a new recording of the page starts its failures over
+        if attempt == 1 && fs::try_exists(&fail).await? {
+            fs::remove_file(&fail).await?;
+        }
+
+        if !raw.status.is_success() {
+            let mut line = raw.status.as_u16().to_string();
+            if let Some(retry_after) = raw.retry_after {
+                line += &format!(" {}", retry_after.as_secs());
+            }
+
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&fail)
+                .await?;
+
+            return file.write_all(format!("{line}\n").as_bytes()).await;
+        }
+
+        let body = match (serde_json::from_slice(&raw.body), &self.pseudonyms) {
+            (Ok(mut json), Some(pseudonyms)) => {
+                anonymise(&mut json, &mut pseudonyms.lock().expect("not poisoned"));
+                serde_json::to_vec_pretty(&json)?
+            }
+            _ => raw.body.clone(),
+        };
+
+        fs::write(self.fixtures.path(url, page, "json"), body).await
+    }
+}
+
+/// Serves fixtures in place of the API, the failures of a page first.
+pub struct Replayer {
+    fixtures: Fixtures,
+    served: Mutex<HashMap<PathBuf, usize>>,
+}
+
+impl Replayer {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            fixtures: Fixtures::new(dir),
+            served: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn get(&self, url: &str, page: u32) -> io::Result<Raw> {
+        let body = self.fixtures.path(url, page, "json");
+
+        let attempt = {
+            let mut served = self.served.lock().expect("not poisoned");
+            let count = served.entry(body.clone()).or_default();
+            *count += 1;
+            *count
+        };
+
+        let fail = self.fixtures.path(url, page, "fail");
+        if fs::try_exists(&fail).await? {
+            let failures = fs::read_to_string(&fail).await?;
+
+            if let Some(line) = failures.lines().nth(attempt - 1) {
+                return parse_failure(line).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{}: bad line `{line}`", fail.display()),
+                    )
+                });
+            }
+        }
+
+        fs::read(&body)
+            .await
+            .map(|body| Raw {
+                status: StatusCode::OK,
+                retry_after: None,
+                body,
+            })
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", body.display())))
+    }
+}
+
+fn parse_failure(line: &str) -> Option<Raw> {
+    let mut parts = line.split_whitespace();
+    let status = StatusCode::from_u16(parts.next()?.parse().ok()?).ok()?;
+    let retry_after = match parts.next() {
+        Some(secs) => Some(Duration::from_secs(secs.parse().ok()?)),
+        None => None,
+    };
+
+    Some(Raw {
+        status,
+        retry_after,
+        body: Vec::new(),
+    })
+}
+
+/// Stand-ins numbered in the order the originals show up, nothing about the
+/// original survives in them.
+#[derive(Default)]
+struct Pseudonyms {
+    slack_ids: HashMap<String, usize>,
+    names: HashMap<String, usize>,
+}
+
+impl Pseudonyms {
+    fn slack_id(&mut self, id: &str) -> String {
+        format!("U{:08}", number(&mut self.slack_ids, id))
+    }
+
+    fn name(&mut self, name: &str) -> String {
+        format!("user {}", number(&mut self.names, name))
+    }
+}
+
+fn number(seen: &mut HashMap<String, usize>, original: &str) -> usize {
+    let next = seen.len() + 1;
+    *seen.entry(original.to_string()).or_insert(next)
+}
+
+/// Swaps every `slack_id` and `display_name` for a stand-in that stays the
+/// same across the recording, drops bios and scrubs links, mentions and
+/// emails out of every other string.
+fn anonymise(value: &mut serde_json::Value, pseudonyms: &mut Pseudonyms) {
+    match value {
+        serde_json::Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match (key.as_str(), value) {
+                    ("slack_id", serde_json::Value::String(id)) => *id = pseudonyms.slack_id(id),
+                    ("display_name", serde_json::Value::String(name)) => {
+                        *name = pseudonyms.name(name);
+                    }
+                    ("bio", serde_json::Value::String(bio)) => *bio = "bio removed".to_string(),
+                    (_, value) => anonymise(value, pseudonyms),
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| anonymise(value, pseudonyms)),
+        serde_json::Value::String(text) => *text = scrub(text, pseudonyms),
+        _ => {}
+    }
+}
+
+/// Links become `https://example.com`, Slack mentions `<@U…>` of the same
+/// stand-ins, other `@handles` `@someone` and emails `someone@example.com`.
+fn scrub(text: &str, pseudonyms: &mut Pseudonyms) -> String {
+    let mut scrubbed = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find(['h', '<', '@']) {
+        let (before, from) = rest.split_at(start);
+        scrubbed += before;
+
+        let token_end = |from: &str| {
+            from.find(|c: char| c.is_whitespace() || "<>()[]{}\"'`|,".contains(c))
+                .unwrap_or(from.len())
+        };
+
+        if from.starts_with("http://") || from.starts_with("https://") {
+            let end = token_end(from);
+            // keep sentence punctuation after the link
+            let end = from[..end]
+                .trim_end_matches(['.', ':', ';', '!', '?'])
+                .len();
+
+            scrubbed += "https://example.com";
+            rest = &from[end..];
+        } else if let Some(mention) = from
+            .strip_prefix("<@")
+            .and_then(|mention| mention.split_once('>'))
+            .filter(|(id, _)| id.chars().all(|c| c.is_ascii_alphanumeric() || c == '|'))
+        {
+            let (id, after) = mention;
+            let id = id.split('|').next().unwrap_or(id);
+
+            scrubbed += &format!("<@{}>", pseudonyms.slack_id(id));
+            rest = after;
+        } else if from.starts_with('@') && token_end(&from[1..]) > 0 {
+            let end = 1 + token_end(&from[1..]);
+            let end = from[..end]
+                .trim_end_matches(['.', ':', ';', '!', '?'])
+                .len();
+
+            // the local part of an email is already in `scrubbed`
+            let local = scrubbed
+                .rfind(|c: char| c.is_whitespace() || "<>()[]{}\"'`|,".contains(c))
+                .map_or(0, |i| i + 1);
+            if local < scrubbed.len() {
+                scrubbed.truncate(local);
+                scrubbed += "someone@example.com";
+            } else {
+                scrubbed += "@someone";
+            }
+            rest = &from[end..];
+        } else {
+            let c = from.chars().next().expect("found a char");
+            scrubbed.push(c);
+            rest = &from[c.len_utf8()..];
+        }
+    }
+
+    scrubbed + rest
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn scrubbed(text: &str) -> String {
+        scrub(text, &mut Pseudonyms::default())
+    }
+
+    #[test]
+    fn pseudonyms_are_sequential_and_stable() {
+        let mut value = json!({
+            "devlogs": [
+                {"slack_id": "U08ALICE", "text": "hi"},
+                {"slack_id": "U07BOB", "text": "cc <@U08ALICE>"},
+                {"slack_id": "U08ALICE", "text": "again"},
+            ],
+        });
+        anonymise(&mut value, &mut Pseudonyms::default());
+
+        assert_eq!(
+            value,
+            json!({
+                "devlogs": [
+                    {"slack_id": "U00000001", "text": "hi"},
+                    {"slack_id": "U00000002", "text": "cc <@U00000001>"},
+                    {"slack_id": "U00000001", "text": "again"},
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn profiles_are_dropped() {
+        let mut value = json!({
+            "users": [{
+                "id": 4,
+                "slack_id": "U08ALICE",
+                "display_name": "Alice Liddell",
+                "bio": "I live in Oxford, say hi at alice@wonder.land",
+            }],
+        });
+        anonymise(&mut value, &mut Pseudonyms::default());
+
+        assert_eq!(
+            value,
+            json!({
+                "users": [{
+                    "id": 4,
+                    "slack_id": "U00000001",
+                    "display_name": "user 1",
+                    "bio": "bio removed",
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn links_mentions_and_emails_are_scrubbed() {
+        assert_eq!(
+            scrubbed("see https://github.com/alice/tea-party."),
+            "see https://example.com."
+        );
+        assert_eq!(
+            scrubbed("[repo](http://alice.dev/x?y=1) and (https://hack.club)"),
+            "[repo](https://example.com) and (https://example.com)"
+        );
+        assert_eq!(
+            scrubbed("thanks @bob_builder! mail hannah.b@gmail.com"),
+            "thanks @someone! mail someone@example.com"
+        );
+        assert_eq!(scrubbed("<@U07BOB|bob> helped"), "<@U00000001> helped");
+
+        // nothing to scrub
+        let plain = "the hat is at home <3 @ noon, 100% höhe";
+        assert_eq!(scrubbed(plain), plain);
+    }
+
+    #[test]
+    fn off_keeps_responses_as_sent() {
+        assert!(
+            Recorder::new(Path::new("fixtures"), false)
+                .pseudonyms
+                .is_none()
+        );
+    }
+}
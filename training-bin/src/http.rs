@@ -0,0 +1,533 @@
+use std::fmt::{self, Display};
+use std::time::{Duration, SystemTime};
+
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Response, StatusCode};
+use serde::de::DeserializeOwned;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::config::Fetch;
+use crate::fixtures::{Recorder, Replayer};
+
+// first retry waits around this long, doubling after
+const BACKOFF: Duration = Duration::from_millis(500);
+// longest wait between tries, however far the backoff or Retry-After goes
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Shared client for paginated APIs: retries, rate limit, concurrency and
+/// fixtures from `[fetch]`.
+pub struct Fetcher {
+    transport: Transport,
+    recorder: Option<Recorder>,
+    attempts: usize,
+    pub concurrency: usize,
+    limiter: Option<RateLimiter>,
+}
+
+enum Transport {
+    Http(reqwest::Client),
+    Replay(Replayer),
+}
+
+/// A response before decoding, what fixtures store.
+pub struct Raw {
+    pub status: StatusCode,
+    pub retry_after: Option<Duration>,
+    pub body: Vec<u8>,
+}
+
+impl Fetcher {
+    pub fn new(settings: &Fetch, headers: HeaderMap) -> anyhow::Result<Self> {
+        let transport = match (&settings.record, &settings.replay) {
+            (Some(_), Some(_)) => anyhow::bail!("fetch.record and fetch.replay are exclusive"),
+            (_, Some(dir)) => Transport::Replay(Replayer::new(dir)),
+            _ => Transport::Http(
+                reqwest::Client::builder()
+                    .default_headers(headers)
+                    .timeout(Duration::from_secs(settings.timeout_secs))
+                    .build()?,
+            ),
+        };
+
+        Ok(Self {
+            transport,
+            recorder: settings
+                .record
+                .as_deref()
+                .map(|dir| Recorder::new(dir, settings.anonymise)),
+            attempts: settings.attempts.max(1),
+            concurrency: settings.concurrency.max(1),
+            limiter: settings.requests_per_second.map(RateLimiter::new),
+        })
+    }
+
+    /// `{url}?page={page}` as JSON. Network errors, timeouts, 408, 429 and 5xx
+    /// are retried with jittered exponential backoff, or after `Retry-After`
+    /// when the server sends one, never waiting more than a minute. Other
+    /// statuses fail right away. Replays don't wait between attempts.
+    pub async fn page<T: DeserializeOwned>(&self, url: &str, page: u32) -> Result<T, PageError> {
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+
+            if let Some(limiter) = &self.limiter {
+                limiter.wait().await;
+            }
+
+            let (cause, retry_after) = match self.get(url, page, attempt).await {
+                Ok(raw) if raw.status.is_success() => {
+                    match serde_json::from_slice::<T>(&raw.body) {
+                        Ok(json) => return Ok(json),
+                        Err(err) => (Cause::Decode(err), None),
+                    }
+                }
+                Ok(raw) if retryable(raw.status) => (Cause::Status(raw.status), raw.retry_after),
+                Ok(raw) => (Cause::Status(raw.status), None),
+                Err(err) => (err, None),
+            };
+
+            let fatal = match &cause {
+                Cause::Status(status) => !retryable(*status),
+                Cause::Fixture(_) => true,
+                Cause::Request(_) | Cause::Decode(_) => false,
+            };
+
+            if fatal || attempt >= self.attempts {
+                return Err(PageError {
+                    url: url.to_string(),
+                    page,
+                    attempts: attempt,
+                    cause,
+                });
+            }
+
+            if matches!(self.transport, Transport::Replay(_)) {
+                println!("{url} page {page}: {cause}, retrying");
+                continue;
+            }
+
+            let delay = retry_delay(retry_after, attempt);
+            println!(
+                "{url} page {page}: {cause}, retrying in {}ms",
+                delay.as_millis()
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    async fn get(&self, url: &str, page: u32, attempt: usize) -> Result<Raw, Cause> {
+        let client = match &self.transport {
+            Transport::Http(client) => client,
+            Transport::Replay(replayer) => {
+                return replayer.get(url, page).await.map_err(Cause::Fixture);
+            }
+        };
+
+        let resp = client
+            .get(format!("{url}?page={page}"))
+            .send()
+            .await
+            .map_err(Cause::Request)?;
+
+        let raw = Raw {
+            status: resp.status(),
+            retry_after: retry_after(&resp),
+            body: resp.bytes().await.map_err(Cause::Request)?.to_vec(),
+        };
+
+        if let Some(recorder) = &self.recorder {
+            recorder
+                .save(url, page, attempt, &raw)
+                .await
+                .map_err(Cause::Fixture)?;
+        }
+
+        Ok(raw)
+    }
+}
+
+/// Spaces requests evenly across every task sharing the fetcher.
+struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    fn new(requests_per_second: f64) -> Self {
+        Self {
+            interval: Duration::from_secs_f64(1. / requests_per_second.max(f64::EPSILON)),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    async fn wait(&self) {
+        let mut next = self.next.lock().await;
+
+        tokio::time::sleep_until(*next).await;
+        *next = Instant::now().max(*next) + self.interval;
+    }
+}
+
+/// A page that couldn't be fetched, after `attempts` tries.
+#[derive(Debug)]
+pub struct PageError {
+    pub url: String,
+    pub page: u32,
+    pub attempts: usize,
+    pub cause: Cause,
+}
+
+#[derive(Debug)]
+pub enum Cause {
+    Status(StatusCode),
+    Request(reqwest::Error),
+    Decode(serde_json::Error),
+    /// Reading or writing `fetch.record` / `fetch.replay`
+    Fixture(std::io::Error),
+}
+
+impl Display for Cause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Status(status) => write!(f, "HTTP {status}"),
+            Self::Request(err) => write!(f, "request failed: {err}"),
+            Self::Decode(err) => write!(f, "bad JSON: {err}"),
+            Self::Fixture(err) => write!(f, "fixture: {err}"),
+        }
+    }
+}
+
+impl Display for PageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "page {} of {} failed", self.page, self.url)?;
+        if self.attempts > 1 {
+            write!(f, " after {} attempts", self.attempts)?;
+        }
+
+        write!(f, ": {}", self.cause)
+    }
+}
+
+// the cause is already part of the message
+impl std::error::Error for PageError {}
+
+fn retryable(status: StatusCode) -> bool {
+    status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status.is_server_error()
+}
+
+/// Seconds or an HTTP date.
+fn retry_after(resp: &Response) -> Option<Duration> {
+    let value = resp.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    match value.parse::<u64>() {
+        Ok(secs) => Some(Duration::from_secs(secs)),
+        Err(_) => httpdate::parse_http_date(value)
+            .ok()?
+            .duration_since(SystemTime::now())
+            .ok(),
+    }
+}
+
+/// What the server asked for, or the backoff, capped at [`MAX_BACKOFF`].
+fn retry_delay(retry_after: Option<Duration>, attempt: usize) -> Duration {
+    retry_after
+        .unwrap_or_else(|| backoff(attempt))
+        .min(MAX_BACKOFF)
+}
+
+/// Between half and all of `BACKOFF * 2^(attempt - 1)`, so parallel pages
+/// don't retry in lockstep.
+fn backoff(attempt: usize) -> Duration {
+    let full = BACKOFF * 2_u32.pow((attempt as u32 - 1).min(10));
+
+    full / 2 + full.mul_f64(rand::rng().random::<f64>() / 2.)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex as StdMutex};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// What the test server answers with, after waiting `delay`.
+    pub(crate) struct Reply {
+        pub status: u16,
+        pub headers: Vec<(&'static str, String)>,
+        pub body: String,
+        pub delay: Duration,
+    }
+
+    impl Reply {
+        pub(crate) fn json(body: impl Into<String>) -> Self {
+            Self::status(200).body(body)
+        }
+
+        pub(crate) fn status(status: u16) -> Self {
+            Self {
+                status,
+                headers: Vec::new(),
+                body: String::new(),
+                delay: Duration::ZERO,
+            }
+        }
+
+        pub(crate) fn body(self, body: impl Into<String>) -> Self {
+            Self {
+                body: body.into(),
+                ..self
+            }
+        }
+
+        pub(crate) fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+            self.headers.push((name, value.into()));
+            self
+        }
+
+        pub(crate) fn delay(self, delay: Duration) -> Self {
+            Self { delay, ..self }
+        }
+    }
+
+    #[derive(Default)]
+    pub(crate) struct Log {
+        /// Path, page and when it arrived, in arrival order
+        pub requests: Vec<(String, u32, Instant)>,
+        pub max_in_flight: usize,
+        in_flight: usize,
+        hits: HashMap<(String, u32), usize>,
+    }
+
+    impl Log {
+        pub(crate) fn pages(&self, path: &str) -> Vec<u32> {
+            self.requests
+                .iter()
+                .filter(|(at, ..)| at == path)
+                .map(|&(_, page, _)| page)
+                .collect()
+        }
+    }
+
+    type Handler = dyn Fn(&str, u32, usize) -> Reply + Send + Sync;
+
+    /// HTTP/1.1 on `127.0.0.1:0`, answering `GET {path}?page={page}` with
+    /// `handler(path, page, nth request for that page)`.
+    pub(crate) struct Server {
+        pub url: String,
+        pub log: Arc<StdMutex<Log>>,
+    }
+
+    impl Server {
+        pub(crate) async fn start(
+            handler: impl Fn(&str, u32, usize) -> Reply + Send + Sync + 'static,
+        ) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let log = Arc::new(StdMutex::new(Log::default()));
+            let handler: Arc<Handler> = Arc::new(handler);
+
+            let shared = log.clone();
+            tokio::spawn(async move {
+                loop {
+                    let (stream, _) = listener.accept().await.unwrap();
+                    tokio::spawn(serve(stream, handler.clone(), shared.clone()));
+                }
+            });
+
+            Self { url, log }
+        }
+
+        pub(crate) fn log(&self) -> std::sync::MutexGuard<'_, Log> {
+            self.log.lock().unwrap()
+        }
+    }
+
+    async fn serve(
+        mut stream: tokio::net::TcpStream,
+        handler: Arc<Handler>,
+        log: Arc<StdMutex<Log>>,
+    ) {
+        let mut request = Vec::new();
+        let mut buf = [0; 1024];
+        while !request.ends_with(b"\r\n\r\n") {
+            match stream.read(&mut buf).await {
+                Ok(0) | Err(_) => return,
+                Ok(n) => request.extend_from_slice(&buf[..n]),
+            }
+        }
+
+        let request = String::from_utf8_lossy(&request);
+        let target = request.split(' ').nth(1).unwrap_or_default();
+        let (path, page) = target.split_once("?page=").unwrap_or((target, "1"));
+        let (path, page) = (path.to_string(), page.parse().unwrap_or(1));
+
+        let nth = {
+            let mut log = log.lock().unwrap();
+            log.requests.push((path.clone(), page, Instant::now()));
+            log.in_flight += 1;
+            log.max_in_flight = log.max_in_flight.max(log.in_flight);
+
+            let hits = log.hits.entry((path.clone(), page)).or_default();
+            *hits += 1;
+            *hits
+        };
+
+        let reply = handler(&path, page, nth);
+        tokio::time::sleep(reply.delay).await;
+        log.lock().unwrap().in_flight -= 1;
+
+        let mut response = format!(
+            "HTTP/1.1 {} Test\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+            reply.status,
+            reply.body.len()
+        );
+        for (name, value) in &reply.headers {
+            response += &format!("{name}: {value}\r\n");
+        }
+        response += "\r\n";
+        response += &reply.body;
+
+        let _ = stream.write_all(response.as_bytes()).await;
+        let _ = stream.shutdown().await;
+    }
+
+    pub(crate) fn fetcher(settings: Fetch) -> Fetcher {
+        Fetcher::new(&settings, HeaderMap::new()).unwrap()
+    }
+
+    fn gaps(log: &Log) -> Vec<Duration> {
+        log.requests
+            .windows(2)
+            .map(|pair| pair[1].2 - pair[0].2)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn retries_after_the_servers_wait() {
+        let server = Server::start(|_, _, nth| match nth {
+            1 => Reply::status(429).header("Retry-After", "1"),
+            _ => Reply::json("7"),
+        })
+        .await;
+
+        let page: u32 = fetcher(Fetch::default())
+            .page(&server.url, 1)
+            .await
+            .unwrap();
+
+        assert_eq!(page, 7);
+        assert!(gaps(&server.log())[0] >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn backs_off_on_server_errors() {
+        let server = Server::start(|_, _, nth| match nth {
+            1 => Reply::status(500),
+            2 => Reply::status(503),
+            _ => Reply::json("[]"),
+        })
+        .await;
+
+        let page: Vec<u32> = fetcher(Fetch::default())
+            .page(&server.url, 1)
+            .await
+            .unwrap();
+        assert!(page.is_empty());
+
+        // at least half of 500ms, then of 1s
+        let gaps = gaps(&server.log());
+        assert!(gaps[0] >= BACKOFF / 2, "{gaps:?}");
+        assert!(gaps[1] >= BACKOFF, "{gaps:?}");
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_attempts() {
+        let server = Server::start(|_, _, _| Reply::status(502)).await;
+
+        let err = fetcher(Fetch {
+            attempts: 2,
+            ..Fetch::default()
+        })
+        .page::<u32>(&server.url, 3)
+        .await
+        .unwrap_err();
+
+        assert_eq!(err.attempts, 2);
+        assert!(matches!(err.cause, Cause::Status(StatusCode::BAD_GATEWAY)));
+        assert_eq!(server.log().pages("/"), [3, 3]);
+    }
+
+    #[tokio::test]
+    async fn only_retries_transient_statuses() {
+        let server = Server::start(|_, page, _| match page {
+            1 => Reply::status(401),
+            2 => Reply::status(404),
+            _ => Reply::status(408),
+        })
+        .await;
+        let fetcher = fetcher(Fetch {
+            attempts: 2,
+            ..Fetch::default()
+        });
+
+        for page in 1..=3 {
+            let _ = fetcher.page::<u32>(&server.url, page).await.unwrap_err();
+        }
+
+        assert_eq!(server.log().pages("/"), [1, 2, 3, 3]);
+    }
+
+    #[tokio::test]
+    async fn retries_bad_json() {
+        let server = Server::start(|_, _, nth| match nth {
+            1 => Reply::json("{\"truncated"),
+            _ => Reply::json("1"),
+        })
+        .await;
+
+        let page: u32 = fetcher(Fetch::default())
+            .page(&server.url, 1)
+            .await
+            .unwrap();
+        assert_eq!(page, 1);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_spaces_concurrent_requests() {
+        let server = Server::start(|_, page, _| Reply::json(page.to_string())).await;
+        let fetcher = fetcher(Fetch {
+            requests_per_second: Some(20.),
+            ..Fetch::default()
+        });
+
+        let pages = futures::future::try_join_all(
+            (1..=5).map(|page| fetcher.page::<u32>(&server.url, page)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(pages, [1, 2, 3, 4, 5]);
+
+        // 50ms apart, give or take the clock
+        for gap in gaps(&server.log()) {
+            assert!(gap >= Duration::from_millis(45), "{gap:?}");
+        }
+    }
+
+    #[test]
+    fn waits_are_capped() {
+        assert_eq!(retry_delay(Some(Duration::from_secs(3600)), 1), MAX_BACKOFF);
+        assert_eq!(retry_delay(Some(Duration::from_secs(2)), 1).as_secs(), 2);
+        assert!(retry_delay(None, 30) <= MAX_BACKOFF);
+
+        let first = retry_delay(None, 1);
+        assert!(first >= BACKOFF / 2 && first <= BACKOFF, "{first:?}");
+    }
+}
@@ -0,0 +1,229 @@
+use colored::Colorize;
+use ndarray::{Array1, Array2, s};
+use sonai_metrics::Label;
+
+// share of each seed class that has to land in a cluster with its label
+const MIN_SEED_RECALL: f64 = 0.75;
+
+/// Sum of the registry columns, every one of them is "higher = more AI-like".
+pub fn composite_scores(features: &Array2<f64>, n_metrics: usize) -> Vec<f64> {
+    features
+        .rows()
+        .into_iter()
+        .map(|row| row.slice(s![..n_metrics]).sum())
+        .collect()
+}
+
+/// Clusters scoring above the corpus average are AI. Used when there are no
+/// seeds, and for clusters no seed lands in.
+pub fn composite_labels(composite: &[f64], predicted: &Array1<usize>, k: usize) -> Vec<Label> {
+    let mut sums = vec![0.0f64; k];
+    let mut counts = vec![0usize; k];
+
+    for (&score, &cluster) in composite.iter().zip(predicted) {
+        sums[cluster] += score;
+        counts[cluster] += 1;
+    }
+
+    let averages: Vec<f64> = sums
+        .iter()
+        .zip(&counts)
+        .map(|(sum, &count)| sum / count.max(1) as f64)
+        .collect();
+    let corpus_avg = composite.iter().sum::<f64>() / composite.len().max(1) as f64;
+
+    let mut labels: Vec<Label> = averages
+        .iter()
+        .map(|&avg| {
+            if avg > corpus_avg {
+                Label::Ai
+            } else {
+                Label::Human
+            }
+        })
+        .collect();
+
+    // a flat corpus still gets one AI cluster
+    if !labels.contains(&Label::Ai)
+        && let Some(most) = (0..k).max_by(|&a, &b| averages[a].total_cmp(&averages[b]))
+    {
+        labels[most] = Label::Ai;
+    }
+
+    labels
+}
+
+/// Labels each cluster by the majority of the seeds that land in it, falling
+/// back to `fallback` for clusters without a clear majority.
+pub fn majority_labels(
+    fallback: &[Label],
+    seed_clusters: &Array1<usize>,
+    seeds: &[Label],
+) -> Vec<Label> {
+    tally(fallback.len(), seed_clusters, seeds)
+        .iter()
+        .zip(fallback)
+        .map(|(&(ai, human), &fallback)| match ai.cmp(&human) {
+            std::cmp::Ordering::Greater => Label::Ai,
+            std::cmp::Ordering::Less => Label::Human,
+            std::cmp::Ordering::Equal => fallback,
+        })
+        .collect()
+}
+
+/// Share of the `class` seeds that sit in a cluster labeled `class`.
+pub fn seed_recall(
+    labels: &[Label],
+    seed_clusters: &Array1<usize>,
+    seeds: &[Label],
+    class: Label,
+) -> f64 {
+    let (hits, total) = seed_clusters
+        .iter()
+        .zip(seeds)
+        .filter(|(_, seed)| **seed == class)
+        .fold((0, 0), |(hits, total), (&cluster, _)| {
+            (hits + usize::from(labels[cluster] == class), total + 1)
+        });
+
+    hits as f64 / total.max(1) as f64
+}
+
+/// [`majority_labels`] with a diagnostic table.
+///
+/// Fails when too many seeds end up under the other label, a retrain that
+/// split on some unrelated axis should never ship.
+pub fn seed_labels(
+    fallback: &[Label],
+    seed_clusters: &Array1<usize>,
+    seeds: &[Label],
+) -> anyhow::Result<Vec<Label>> {
+    let tally = tally(fallback.len(), seed_clusters, seeds);
+    let labels = majority_labels(fallback, seed_clusters, seeds);
+
+    println!("\n{}", "Seed labeling".bold().cyan());
+    println!(
+        "{:<8} {:>10} {:>10} {:>10} {:>8}",
+        "cluster", "composite", "ai seeds", "human", "label"
+    );
+
+    for (cluster, (&(ai, human), label)) in tally.iter().zip(&labels).enumerate() {
+        let line = format!(
+            "{cluster:<8} {:>10} {ai:>10} {human:>10} {:>8}",
+            format!("{:?}", fallback[cluster]),
+            format!("{label:?}"),
+        );
+
+        if fallback[cluster] == *label {
+            println!("{line}");
+        } else {
+            println!("{}", line.yellow());
+        }
+    }
+
+    let ai_recall = seed_recall(&labels, seed_clusters, seeds, Label::Ai);
+    let human_recall = seed_recall(&labels, seed_clusters, seeds, Label::Human);
+
+    println!("seed recall: ai={ai_recall:.2} human={human_recall:.2}");
+
+    if !labels.contains(&Label::Ai) || !labels.contains(&Label::Human) {
+        anyhow::bail!(
+            "every cluster got the same label {labels:?}, the clusters don't separate the seeds"
+        );
+    }
+
+    if ai_recall < MIN_SEED_RECALL || human_recall < MIN_SEED_RECALL {
+        anyhow::bail!(
+            "seed set disagrees with the labeling (ai recall {ai_recall:.2}, human recall {human_recall:.2}, need {MIN_SEED_RECALL}), refusing to export"
+        );
+    }
+
+    Ok(labels)
+}
+
+/// (ai, human) seed counts per cluster.
+fn tally(k: usize, seed_clusters: &Array1<usize>, seeds: &[Label]) -> Vec<(usize, usize)> {
+    let mut tally = vec![(0usize, 0usize); k];
+
+    for (&cluster, &label) in seed_clusters.iter().zip(seeds) {
+        match label {
+            Label::Ai => tally[cluster].0 += 1,
+            Label::Human => tally[cluster].1 += 1,
+        }
+    }
+
+    tally
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::array;
+
+    use super::*;
+    use Label::{Ai, Human};
+
+    #[test]
+    fn clusters_above_the_corpus_average_are_ai() {
+        // cluster averages 1, 5 and 3 against a corpus average of 3
+        let composite = [1., 1., 5., 5., 3., 3.];
+        let predicted = array![0, 0, 1, 1, 2, 2];
+
+        assert_eq!(
+            composite_labels(&composite, &predicted, 3),
+            [Human, Ai, Human]
+        );
+    }
+
+    #[test]
+    fn a_flat_corpus_still_gets_an_ai_cluster() {
+        let labels = composite_labels(&[2., 2., 2., 2.], &array![0, 1, 0, 1], 2);
+
+        assert_eq!(labels.iter().filter(|label| **label == Ai).count(), 1);
+    }
+
+    #[test]
+    fn seeds_outvote_the_fallback() {
+        let seed_clusters = array![0, 0, 1, 2, 2];
+        let seeds = [Human, Human, Ai, Ai, Human];
+
+        // cluster 2 is a tie and keeps its fallback
+        assert_eq!(
+            majority_labels(&[Ai, Human, Ai], &seed_clusters, &seeds),
+            [Human, Ai, Ai]
+        );
+        assert_eq!(
+            majority_labels(&[Ai, Human, Human], &seed_clusters, &seeds),
+            [Human, Ai, Human]
+        );
+    }
+
+    #[test]
+    fn seed_labels_accepts_separated_seeds() {
+        let seed_clusters = array![0, 0, 0, 1, 1, 1, 1];
+        let seeds = [Ai, Ai, Ai, Human, Human, Human, Ai];
+
+        // 3 of 4 ai seeds sit in the ai cluster, exactly the minimum
+        assert_eq!(
+            seed_labels(&[Human, Ai], &seed_clusters, &seeds).unwrap(),
+            [Ai, Human]
+        );
+    }
+
+    #[test]
+    fn seed_labels_bails_below_the_minimum_recall() {
+        // human recall 1/2
+        let seed_clusters = array![0, 0, 0, 1];
+        let seeds = [Ai, Ai, Human, Human];
+
+        let err = seed_labels(&[Ai, Human], &seed_clusters, &seeds).unwrap_err();
+        assert!(err.to_string().contains("refusing to export"), "{err}");
+    }
+
+    #[test]
+    fn seed_labels_bails_on_a_single_label() {
+        let seed_clusters = array![0, 1];
+        let seeds = [Ai, Ai];
+
+        assert!(seed_labels(&[Human, Human], &seed_clusters, &seeds).is_err());
+    }
+}
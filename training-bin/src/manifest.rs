@@ -0,0 +1,162 @@
+use std::path::Path;
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sonai_metrics::Label;
+use tokio::fs;
+
+/// Written next to every trained model, enough to tell what produced it and
+/// for `verify` to produce it again.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub created: String,
+    pub training_version: String,
+    pub sonai_metrics_version: String,
+    pub git_commit: Option<String>,
+    /// Uncommitted changes when the run happened
+    pub git_dirty: bool,
+    /// Cargo.lock, pins every dependency version
+    pub lockfile_sha256: Option<String>,
+    pub lexicon_sha256: String,
+    pub seed: u64,
+    /// The config file as it was, empty when the defaults were used
+    pub config: String,
+    pub corpus: Corpus,
+    pub inputs: Inputs,
+    pub model: ModelSummary,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Corpus {
+    pub sha256: String,
+    pub records: usize,
+}
+
+/// Hashes of the optional input directories.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Inputs {
+    pub lm_sha256: Option<String>,
+    pub seeds_sha256: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModelSummary {
+    pub sha256: String,
+    pub labels: Vec<Label>,
+    pub cluster_sizes: Vec<usize>,
+    /// Centroids, or component means for the mixture
+    pub centers: Vec<Vec<f64>>,
+}
+
+impl Manifest {
+    pub async fn read(path: &Path) -> anyhow::Result<Self> {
+        let text = fs::read_to_string(path).await?;
+
+        toml::from_str(&text).map_err(|err| anyhow::anyhow!("{}: {err}", path.display()))
+    }
+
+    pub async fn write(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, toml::to_string(self)?).await?;
+
+        Ok(())
+    }
+}
+
+pub fn sha256(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
+
+/// File names and contents in name order, so the hash doesn't depend on how
+/// the filesystem lists them.
+pub async fn sha256_dir(dir: &Path) -> anyhow::Result<String> {
+    let mut paths = Vec::new();
+    let mut entries = fs::read_dir(dir).await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        paths.push(entry.path());
+    }
+    paths.sort();
+
+    let mut hasher = Sha256::new();
+
+    for path in paths {
+        if fs::metadata(&path).await?.is_dir() {
+            hasher.update(Box::pin(sha256_dir(&path)).await?);
+        } else {
+            hasher.update(fs::read(&path).await?);
+        }
+        hasher.update(path.file_name().unwrap_or_default().as_encoded_bytes());
+    }
+
+    Ok(hex(&hasher.finalize()))
+}
+
+pub fn lexicon_sha256() -> String {
+    let mut hasher = Sha256::new();
+
+    for (name, source) in sonai_metrics::LEXICON_SOURCES {
+        hasher.update(name);
+        hasher.update(source);
+    }
+
+    hex(&hasher.finalize())
+}
+
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// HEAD and whether the tree is dirty, `None` outside a git checkout.
+pub fn git_state() -> (Option<String>, bool) {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    let commit = git(&["rev-parse", "HEAD"]);
+    let dirty = commit.is_some() && git(&["status", "--porcelain"]).is_some_and(|s| !s.is_empty());
+
+    (commit, dirty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn dir_hash(name: &str, files: &[(&str, &str)]) -> String {
+        let dir = std::env::temp_dir().join(format!("sonai-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir).await;
+        fs::create_dir_all(dir.join("sub")).await.unwrap();
+
+        for (file, contents) in files {
+            fs::write(dir.join(file), contents).await.unwrap();
+        }
+
+        let hash = sha256_dir(&dir).await.unwrap();
+        fs::remove_dir_all(&dir).await.unwrap();
+
+        hash
+    }
+
+    #[tokio::test]
+    async fn dir_hash_covers_names_and_contents() {
+        let files = [("a.txt", "one"), ("b.txt", "two"), ("sub/c.txt", "three")];
+        let hash = dir_hash("hash", &files).await;
+
+        // written in another order
+        let mut reversed = files;
+        reversed.reverse();
+        assert_eq!(dir_hash("reversed", &reversed).await, hash);
+
+        let renamed = [("a.txt", "one"), ("x.txt", "two"), ("sub/c.txt", "three")];
+        assert_ne!(dir_hash("renamed", &renamed).await, hash);
+
+        let edited = [("a.txt", "one"), ("b.txt", "two!"), ("sub/c.txt", "three")];
+        assert_ne!(dir_hash("edited", &edited).await, hash);
+    }
+}
@@ -0,0 +1,211 @@
+use colored::Colorize;
+use linfa::traits::Predict;
+use linfa_nn::distance::Distance;
+use ndarray::{Array1, Array2, Axis};
+use sonai_metrics::{DistanceFunction, DistanceKind, Label};
+use std::ops::RangeInclusive;
+
+use crate::labeling::composite_labels;
+use crate::{KMeansParams, fit_kmeans};
+
+// silhouette is quadratic, score an evenly spaced subset of the corpus
+const SILHOUETTE_SAMPLE: usize = 2000;
+
+/// Prints inertia (elbow) and silhouette for each k, returns the k with the
+/// best silhouette.
+pub fn choose_k(
+    features: &Array2<f64>,
+    distance: &DistanceFunction,
+    candidates: RangeInclusive<usize>,
+    params: &KMeansParams,
+) -> anyhow::Result<usize> {
+    println!("\n{}", "Choosing k".bold().cyan());
+    println!("{:<4} {:>12} {:>11}", "k", "inertia", "silhouette");
+
+    let mut best = (*candidates.start(), f64::NEG_INFINITY);
+
+    for k in candidates {
+        let model = fit_kmeans(features, k, distance.clone(), params)?;
+        let predicted: Array1<usize> = model.predict(features);
+        let score = silhouette(features, &predicted, distance);
+
+        println!("{k:<4} {:>12.2} {score:>11.3}", model.inertia());
+
+        if score > best.1 {
+            best = (k, score);
+        }
+    }
+
+    println!("k={}", best.0);
+
+    Ok(best.0)
+}
+
+/// Fits a model per distance on the same features and prints how each one
+/// splits the corpus, next to the model that is being exported.
+pub fn compare_distances(
+    features: &Array2<f64>,
+    k: usize,
+    composite: &[f64],
+    exported_is_ai: &Array1<bool>,
+    params: &KMeansParams,
+) -> anyhow::Result<()> {
+    println!("\n{}", "Distance comparison".bold().cyan());
+    println!(
+        "{:<12} {:>8} {:>11} {:>10} {:>12}",
+        "distance", "ai %", "silhouette", "inertia", "agreement %"
+    );
+
+    for kind in DistanceKind::ALL {
+        let distance = DistanceFunction::fit(kind, features.view());
+        let model = fit_kmeans(features, k, distance.clone(), params)?;
+        let predicted: Array1<usize> = model.predict(features);
+
+        let labels = composite_labels(composite, &predicted, k);
+        let is_ai = predicted.mapv(|cluster| labels[cluster] == Label::Ai);
+
+        let total = is_ai.len().max(1) as f64;
+        let ai_pct = is_ai.iter().filter(|&&ai| ai).count() as f64 * 100. / total;
+        let agreement = is_ai
+            .iter()
+            .zip(exported_is_ai)
+            .filter(|(a, b)| a == b)
+            .count() as f64
+            * 100.
+            / total;
+
+        println!(
+            "{:<12} {:>8.2} {:>11.3} {:>10.2} {:>12.2}",
+            format!("{kind:?}"),
+            ai_pct,
+            silhouette(features, &predicted, &distance),
+            model.inertia(),
+            agreement
+        );
+    }
+
+    Ok(())
+}
+
+/// How many corpus texts the seeded fit moved away from their unconstrained
+/// cluster, and how many of those changed sides.
+pub fn print_moved(before: &Array1<usize>, after: &Array1<usize>, labels: &[Label]) {
+    let moved = before.iter().zip(after).filter(|(a, b)| a != b).count();
+    let flipped = before
+        .iter()
+        .zip(after)
+        .filter(|&(&a, &b)| labels[a] != labels[b])
+        .count();
+
+    let total = before.len().max(1) as f64;
+
+    println!("\n{}", "Seeded vs unconstrained".bold().cyan());
+    println!(
+        "moved={moved} ({:.2}%) flipped label={flipped} ({:.2}%)",
+        moved as f64 * 100. / total,
+        flipped as f64 * 100. / total
+    );
+
+    let k = labels.len();
+    let mut transitions = vec![vec![0usize; k]; k];
+    for (&a, &b) in before.iter().zip(after) {
+        transitions[a][b] += 1;
+    }
+
+    for (from, row) in transitions.iter().enumerate() {
+        for (to, &count) in row.iter().enumerate() {
+            if from != to && count > 0 {
+                println!(
+                    "  {from} ({:?}) -> {to} ({:?}): {count}",
+                    labels[from], labels[to]
+                );
+            }
+        }
+    }
+}
+
+/// Mean silhouette coefficient, -1 (wrong cluster) to 1 (well separated).
+pub fn silhouette(
+    features: &Array2<f64>,
+    labels: &Array1<usize>,
+    distance: &DistanceFunction,
+) -> f64 {
+    let n_clusters = labels.iter().max().map_or(0, |max| max + 1);
+    let step = features.nrows().div_ceil(SILHOUETTE_SAMPLE).max(1);
+    let sample: Vec<usize> = (0..features.nrows()).step_by(step).collect();
+
+    let mut total = 0.;
+
+    for &i in &sample {
+        let mut sums = vec![0.; n_clusters];
+        let mut counts = vec![0usize; n_clusters];
+
+        for &j in &sample {
+            if i == j {
+                continue;
+            }
+
+            let d = distance.distance(
+                features.index_axis(Axis(0), i),
+                features.index_axis(Axis(0), j),
+            );
+            sums[labels[j]] += d;
+            counts[labels[j]] += 1;
+        }
+
+        let own = labels[i];
+        if counts[own] == 0 {
+            continue; // singleton clusters score 0
+        }
+
+        let a = sums[own] / counts[own] as f64;
+        let b = (0..n_clusters)
+            .filter(|&c| c != own && counts[c] > 0)
+            .map(|c| sums[c] / counts[c] as f64)
+            .fold(f64::INFINITY, f64::min);
+
+        if b.is_finite() {
+            total += (b - a) / a.max(b).max(f64::EPSILON);
+        }
+    }
+
+    total / sample.len().max(1) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `centers.len()` tight blobs of 20 points, far apart.
+    fn blobs(centers: &[[f64; 2]]) -> Array2<f64> {
+        Array2::from_shape_fn((centers.len() * 20, 2), |(i, j)| {
+            let jitter = ((i * 7 + j * 3) % 5) as f64 * 0.1;
+            centers[i / 20][j] + jitter
+        })
+    }
+
+    #[test]
+    fn picks_the_number_of_blobs() {
+        let params = KMeansParams::default();
+
+        for centers in [
+            &[[0., 0.], [10., 0.], [0., 10.]][..],
+            &[[0., 0.], [10., 0.], [0., 10.], [10., 10.]][..],
+        ] {
+            let k = choose_k(&blobs(centers), &DistanceFunction::L2, 2..=6, &params).unwrap();
+
+            assert_eq!(k, centers.len());
+        }
+    }
+
+    #[test]
+    fn silhouette_peaks_at_the_true_split() {
+        let features = blobs(&[[0., 0.], [10., 0.]]);
+        let truth: Array1<usize> = (0..features.nrows()).map(|i| i / 20).collect();
+        // alternating labels, each cluster spans both blobs
+        let mixed: Array1<usize> = (0..features.nrows()).map(|i| i % 2).collect();
+
+        assert!(silhouette(&features, &truth, &DistanceFunction::L2) > 0.9);
+        assert!(silhouette(&features, &mixed, &DistanceFunction::L2) < 0.1);
+    }
+}
@@ -0,0 +1,572 @@
+use std::fmt;
+use std::str::FromStr;
+
+use colored::Colorize;
+use linfa::traits::Predict;
+use ndarray::{Array1, Array2, Axis, s};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::{IndexedRandom, SliceRandom};
+use sonai_metrics::{
+    Clusterer, CovarianceType, DistanceFunction, DistanceKind, FeatureSet, GaussianMixture, Label,
+    Scaler, ScalerKind,
+};
+
+use crate::labeling::{composite_labels, composite_scores, majority_labels, seed_recall};
+use crate::report::silhouette;
+use crate::{KMeansParams, fit_kmeans};
+
+// above this a full grid takes hours, random:N has to be asked for
+const MAX_GRID: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    Grid,
+    /// That many candidates drawn from the grid
+    Random(usize),
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "grid" => Ok(Self::Grid),
+            "random" => Ok(Self::Random(32)),
+            _ => s
+                .strip_prefix("random:")
+                .and_then(|n| n.parse().ok())
+                .map(Self::Random)
+                .ok_or_else(|| format!("unknown search `{s}`, expected grid, random or random:N")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    KMeans,
+    Gmm(CovarianceType),
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::KMeans => write!(f, "kmeans"),
+            Self::Gmm(covariance) => write!(f, "gmm-{}", format!("{covariance:?}").to_lowercase()),
+        }
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "kmeans" => Ok(Self::KMeans),
+            "gmm" => Ok(Self::Gmm(CovarianceType::Full)),
+            _ => s
+                .strip_prefix("gmm-")
+                .ok_or_else(|| {
+                    format!("unknown algorithm `{s}`, expected kmeans or gmm[-covariance]")
+                })?
+                .parse()
+                .map(Self::Gmm),
+        }
+    }
+}
+
+impl Algorithm {
+    const ALL: [Self; 3] = [
+        Self::KMeans,
+        Self::Gmm(CovarianceType::Diagonal),
+        Self::Gmm(CovarianceType::Full),
+    ];
+}
+
+/// One point of the search space.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub k: usize,
+    pub algorithm: Algorithm,
+    pub distance: DistanceKind,
+    pub scaler: ScalerKind,
+    pub features: FeatureSet,
+}
+
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "k={} {} {:?} {:?} {}",
+            self.k,
+            self.algorithm,
+            self.distance,
+            self.scaler,
+            blocks(&self.features)
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub candidate: Candidate,
+    /// Held-out balanced accuracy on the seeds, only with a labeled set
+    pub accuracy: Option<f64>,
+    /// Held-out silhouette of the candidate's clusters, measured in the same
+    /// reference space for every candidate
+    pub silhouette: f64,
+}
+
+impl SearchResult {
+    /// What the ranking sorts by, accuracy when there are seeds to score with.
+    pub fn score(&self) -> f64 {
+        self.accuracy.unwrap_or(self.silhouette)
+    }
+}
+
+/// Corpus rows and optional labeled seeds, unscaled.
+pub struct SearchData<'a> {
+    pub corpus: &'a Array2<f64>,
+    /// Layout of `corpus`, candidates drop blocks from it
+    pub features: &'a FeatureSet,
+    pub seeds: Option<(&'a Array2<f64>, &'a [Label])>,
+    /// Registry scales, the fixed scaler's weights
+    pub scales: &'a [f64],
+}
+
+/// Cross-validates every candidate with `folds` folds and returns them best
+/// first. Candidates that fail to fit are reported and left out.
+///
+/// Fails up front when a full grid has more than [`MAX_GRID`] candidates.
+pub fn search(
+    data: &SearchData,
+    strategy: Strategy,
+    folds: usize,
+    params: &KMeansParams,
+) -> anyhow::Result<Vec<SearchResult>> {
+    let mut rng = StdRng::seed_from_u64(params.seed);
+
+    let grid = grid(data.features);
+    let candidates: Vec<Candidate> = match strategy {
+        Strategy::Grid if grid.len() > MAX_GRID => anyhow::bail!(
+            "the grid has {} candidates ({} fits), more than {MAX_GRID}, use search = \"random:N\" or enable fewer feature blocks",
+            grid.len(),
+            grid.len() * folds
+        ),
+        Strategy::Grid => grid,
+        Strategy::Random(n) => grid.choose_multiple(&mut rng, n).cloned().collect(),
+    };
+
+    println!(
+        "\n{}",
+        format!(
+            "Searching {} candidates, {folds}-fold cross-validation ({} fits)",
+            candidates.len(),
+            candidates.len() * folds
+        )
+        .bold()
+        .cyan()
+    );
+
+    let corpus_folds = assign_folds(data.corpus.nrows(), folds, &mut rng);
+    let seed_folds = data
+        .seeds
+        .map(|(_, truth)| stratified_folds(truth, folds, &mut rng));
+
+    let mut results = Vec::with_capacity(candidates.len());
+
+    for (i, candidate) in candidates.into_iter().enumerate() {
+        let mut accuracies = Vec::new();
+        let mut silhouettes = Vec::new();
+
+        let outcome: anyhow::Result<()> = (0..folds).try_for_each(|fold| {
+            let (accuracy, silhouette) = evaluate(
+                data,
+                &candidate,
+                &corpus_folds,
+                seed_folds.as_deref(),
+                fold,
+                params,
+            )?;
+
+            accuracies.extend(accuracy);
+            silhouettes.push(silhouette);
+
+            Ok(())
+        });
+
+        if let Err(err) = outcome {
+            println!("{}", format!("[{}] {candidate} failed: {err}", i + 1).red());
+            continue;
+        }
+
+        let result = SearchResult {
+            candidate,
+            accuracy: (!accuracies.is_empty()).then(|| mean(&accuracies)),
+            silhouette: mean(&silhouettes),
+        };
+
+        println!(
+            "[{}] {} score={:.3}",
+            i + 1,
+            result.candidate,
+            result.score()
+        );
+        results.push(result);
+    }
+
+    results.sort_by(|a, b| {
+        b.score()
+            .total_cmp(&a.score())
+            .then(b.silhouette.total_cmp(&a.silhouette))
+    });
+
+    Ok(results)
+}
+
+/// Tab separated, one row per result in rank order.
+pub fn results_table(results: &[SearchResult]) -> String {
+    let mut table =
+        String::from("rank\tk\talgorithm\tdistance\tscaler\tfeatures\taccuracy\tsilhouette\n");
+
+    for (rank, result) in results.iter().enumerate() {
+        let candidate = &result.candidate;
+
+        table.push_str(&format!(
+            "{}\t{}\t{}\t{:?}\t{:?}\t{}\t{}\t{:.4}\n",
+            rank + 1,
+            candidate.k,
+            candidate.algorithm,
+            candidate.distance,
+            candidate.scaler,
+            blocks(&candidate.features),
+            result
+                .accuracy
+                .map_or_else(|| "-".to_string(), |accuracy| format!("{accuracy:.4}")),
+            result.silhouette,
+        ));
+    }
+
+    table
+}
+
+/// Every k, algorithm, distance, scaler and subset of the optional blocks
+/// enabled in `base`. Perplexity and embeddings stay as they are, they need
+/// models that were loaded for the whole run.
+fn grid(base: &FeatureSet) -> Vec<Candidate> {
+    let toggles = [
+        base.rhythm,
+        base.lexical,
+        base.function_words.is_some(),
+        base.punctuation,
+    ];
+    let enabled: Vec<usize> = (0..toggles.len()).filter(|&i| toggles[i]).collect();
+
+    let subsets: Vec<FeatureSet> = (0..1usize << enabled.len())
+        .map(|mask| {
+            let on = |block: usize| {
+                enabled
+                    .iter()
+                    .position(|&i| i == block)
+                    .is_some_and(|bit| mask & (1 << bit) != 0)
+            };
+
+            FeatureSet {
+                rhythm: on(0),
+                lexical: on(1),
+                function_words: base.function_words.clone().filter(|_| on(2)),
+                punctuation: on(3),
+                ..base.clone()
+            }
+        })
+        .collect();
+
+    let mut grid = Vec::new();
+
+    for k in 2..=6 {
+        for algorithm in Algorithm::ALL {
+            // a mixture only uses the distance for its KMeans start
+            let distances: &[DistanceKind] = match algorithm {
+                Algorithm::KMeans => &DistanceKind::ALL,
+                Algorithm::Gmm(_) => &[DistanceKind::L2],
+            };
+
+            for &distance in distances {
+                for scaler in ScalerKind::ALL {
+                    for features in &subsets {
+                        grid.push(Candidate {
+                            k,
+                            algorithm,
+                            distance,
+                            scaler,
+                            features: features.clone(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    grid
+}
+
+/// Fits on every fold but `fold` and scores on `fold`.
+fn evaluate(
+    data: &SearchData,
+    candidate: &Candidate,
+    corpus_folds: &[usize],
+    seed_folds: Option<&[usize]>,
+    fold: usize,
+    params: &KMeansParams,
+) -> anyhow::Result<(Option<f64>, f64)> {
+    let columns = data
+        .features
+        .columns_of(&candidate.features, data.scales.len());
+    let rows = |held_out: bool| -> Vec<usize> {
+        (0..corpus_folds.len())
+            .filter(|&i| (corpus_folds[i] == fold) == held_out)
+            .collect()
+    };
+
+    let corpus = data.corpus.select(Axis(1), &columns);
+
+    let weights: Vec<f64> = data
+        .scales
+        .iter()
+        .copied()
+        .chain(std::iter::repeat_n(
+            1.,
+            candidate.features.n_block_features(),
+        ))
+        .collect();
+
+    let raw = corpus.select(Axis(0), &rows(false));
+    let scaler = Scaler::fit(candidate.scaler, raw.view(), &weights);
+    let train_x = scaler.transform(raw.view());
+    let test_x = scaler.transform(corpus.select(Axis(0), &rows(true)).view());
+
+    let distance = DistanceFunction::fit(candidate.distance, train_x.view());
+    let kmeans = fit_kmeans(&train_x, candidate.k, distance.clone(), params)?;
+    let predicted: Array1<usize> = kmeans.predict(&train_x);
+
+    let clusterer = match candidate.algorithm {
+        Algorithm::KMeans => Clusterer::KMeans {
+            kmeans,
+            distance: distance.clone(),
+        },
+        Algorithm::Gmm(covariance) => Clusterer::Gmm(GaussianMixture::fit(
+            train_x.view(),
+            &predicted,
+            covariance,
+            1000,
+            1e-6,
+        )),
+    };
+
+    let train_clusters = clusterer.predict(train_x.view());
+    let composite = composite_scores(&train_x, data.scales.len());
+    let mut labels = composite_labels(&composite, &train_clusters, candidate.k);
+
+    let accuracy = match (data.seeds, seed_folds) {
+        (Some((seeds, truth)), Some(seed_folds)) => {
+            let seeds_x = scaler.transform(seeds.select(Axis(1), &columns).view());
+            let seed_clusters = clusterer.predict(seeds_x.view());
+
+            let (train_clusters, train_truth): (Vec<usize>, Vec<Label>) = seed_clusters
+                .iter()
+                .zip(truth)
+                .zip(seed_folds)
+                .filter(|&(_, &f)| f != fold)
+                .map(|((&cluster, &label), _)| (cluster, label))
+                .unzip();
+            let (test_clusters, test_truth): (Vec<usize>, Vec<Label>) = seed_clusters
+                .iter()
+                .zip(truth)
+                .zip(seed_folds)
+                .filter(|&(_, &f)| f == fold)
+                .map(|((&cluster, &label), _)| (cluster, label))
+                .unzip();
+
+            labels = majority_labels(&labels, &Array1::from(train_clusters), &train_truth);
+
+            balanced_recall(&labels, &Array1::from(test_clusters), &test_truth)
+        }
+        _ => None,
+    };
+
+    // silhouettes under different scalers, distances and feature subsets
+    // aren't comparable, so every candidate's clusters are measured on the
+    // base columns, z-scored on the training rows, under L2
+    let reference = data.corpus.slice(s![.., ..data.scales.len()]);
+    let reference_scaler = Scaler::fit(
+        ScalerKind::ZScore,
+        reference.select(Axis(0), &rows(false)).view(),
+        data.scales,
+    );
+    let reference_x = reference_scaler.transform(reference.select(Axis(0), &rows(true)).view());
+
+    let test_clusters = clusterer.predict(test_x.view());
+
+    Ok((
+        accuracy,
+        silhouette(&reference_x, &test_clusters, &DistanceFunction::L2),
+    ))
+}
+
+/// Mean recall over the classes that have seeds in the fold, a class that
+/// happens to be missing says nothing about the candidate.
+fn balanced_recall(labels: &[Label], clusters: &Array1<usize>, truth: &[Label]) -> Option<f64> {
+    let recalls: Vec<f64> = [Label::Ai, Label::Human]
+        .into_iter()
+        .filter(|class| truth.contains(class))
+        .map(|class| seed_recall(labels, clusters, truth, class))
+        .collect();
+
+    (!recalls.is_empty()).then(|| mean(&recalls))
+}
+
+/// Shuffled round robin, fold sizes differ by at most one. `folds` is at
+/// least 2, see [`crate::config::Train::folds`].
+fn assign_folds(n: usize, folds: usize, rng: &mut StdRng) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..n).collect();
+    order.shuffle(rng);
+
+    let mut folds_of = vec![0; n];
+    for (position, &i) in order.iter().enumerate() {
+        folds_of[i] = position % folds;
+    }
+
+    folds_of
+}
+
+/// [`assign_folds`] per class, so every fold sees both classes when each has
+/// at least `folds` seeds.
+fn stratified_folds(truth: &[Label], folds: usize, rng: &mut StdRng) -> Vec<usize> {
+    let mut folds_of = vec![0; truth.len()];
+
+    for class in [Label::Ai, Label::Human] {
+        let members: Vec<usize> = (0..truth.len()).filter(|&i| truth[i] == class).collect();
+
+        for (i, fold) in members.iter().zip(assign_folds(members.len(), folds, rng)) {
+            folds_of[*i] = fold;
+        }
+    }
+
+    folds_of
+}
+
+fn blocks(features: &FeatureSet) -> String {
+    let names: Vec<&str> = [
+        (features.rhythm, "rhythm"),
+        (features.lexical, "lexical"),
+        (features.function_words.is_some(), "function-words"),
+        (features.punctuation, "punctuation"),
+        (features.perplexity, "perplexity"),
+        (features.embeddings > 0, "embeddings"),
+    ]
+    .into_iter()
+    .filter_map(|(on, name)| on.then_some(name))
+    .collect();
+
+    if names.is_empty() {
+        "base".to_string()
+    } else {
+        names.join("+")
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len().max(1) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_covers_every_combination() {
+        // kmeans under every distance, two mixtures under l2
+        let per_subset = 5 * (DistanceKind::ALL.len() + 2) * ScalerKind::ALL.len();
+        assert_eq!(grid(&FeatureSet::default()).len(), per_subset);
+
+        let all_blocks = FeatureSet {
+            rhythm: true,
+            lexical: true,
+            function_words: Some(vec!["the".to_string()]),
+            punctuation: true,
+            ..FeatureSet::default()
+        };
+        assert_eq!(grid(&all_blocks).len(), per_subset * 16);
+    }
+
+    #[test]
+    fn large_grids_need_random() {
+        let corpus = Array2::zeros((10, 1));
+        let features = FeatureSet {
+            rhythm: true,
+            lexical: true,
+            ..FeatureSet::default()
+        };
+        let data = SearchData {
+            corpus: &corpus,
+            features: &features,
+            seeds: None,
+            scales: &[1.],
+        };
+
+        assert!(grid(&features).len() > MAX_GRID);
+        assert!(search(&data, Strategy::Grid, 2, &KMeansParams::default()).is_err());
+    }
+
+    #[test]
+    fn folds_are_even() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let folds = assign_folds(23, 5, &mut rng);
+
+        let sizes: Vec<usize> = (0..5)
+            .map(|fold| folds.iter().filter(|&&f| f == fold).count())
+            .collect();
+        assert_eq!(sizes.iter().sum::<usize>(), 23);
+        assert!(sizes.iter().max().unwrap() - sizes.iter().min().unwrap() <= 1);
+    }
+
+    #[test]
+    fn stratified_folds_hold_both_labels() {
+        let mut rng = StdRng::seed_from_u64(1);
+        // lopsided on purpose, a plain shuffle would leave folds without ai
+        let truth: Vec<Label> = std::iter::repeat_n(Label::Ai, 5)
+            .chain(std::iter::repeat_n(Label::Human, 45))
+            .collect();
+
+        let folds = stratified_folds(&truth, 5, &mut rng);
+
+        for fold in 0..5 {
+            for class in [Label::Ai, Label::Human] {
+                assert!(
+                    folds
+                        .iter()
+                        .zip(&truth)
+                        .any(|(&f, &label)| f == fold && label == class),
+                    "fold {fold} has no {class:?} seed"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn balanced_recall_averages_present_classes() {
+        let labels = [Label::Ai, Label::Human];
+
+        // ai recall 1/2, human recall 1
+        let clusters = Array1::from(vec![0, 1, 1]);
+        let truth = [Label::Ai, Label::Ai, Label::Human];
+        assert_eq!(balanced_recall(&labels, &clusters, &truth), Some(0.75));
+
+        // a fold without ai seeds is scored on the human ones only
+        let clusters = Array1::from(vec![1, 0]);
+        let truth = [Label::Human, Label::Human];
+        assert_eq!(balanced_recall(&labels, &clusters, &truth), Some(0.5));
+
+        assert_eq!(balanced_recall(&labels, &Array1::from(vec![]), &[]), None);
+    }
+}
@@ -0,0 +1,161 @@
+use linfa_nn::distance::Distance;
+use ndarray::{Array1, Array2, ArrayView2, Axis};
+use sonai_metrics::{DistanceFunction, Label, nearest_centroid};
+
+pub struct SeededFit {
+    pub centroids: Array2<f64>,
+    /// Cluster of every corpus row
+    pub predicted: Array1<usize>,
+    pub iterations: usize,
+}
+
+/// Seeded KMeans. The labeled `seeds` start the centroids and can only ever
+/// be assigned to a cluster carrying their own label, unlabeled rows go to the
+/// nearest centroid as usual.
+///
+/// `initial` and `labels` come from the unconstrained fit, clusters no seed
+/// lands in keep their unconstrained centroid as a start.
+pub fn fit_seeded(
+    features: &Array2<f64>,
+    seeds: &Array2<f64>,
+    seed_truth: &[Label],
+    initial: ArrayView2<f64>,
+    labels: &[Label],
+    distance: &DistanceFunction,
+    max_iterations: usize,
+) -> SeededFit {
+    let mut centroids = initial.to_owned();
+
+    let mut seed_clusters = assign_seeds(seeds, seed_truth, &centroids, labels, distance);
+    centroids = update(&centroids, [(seeds, &seed_clusters)]);
+
+    let mut predicted = assign(features, &centroids, distance);
+    let mut iterations = 0;
+
+    while iterations < max_iterations {
+        iterations += 1;
+
+        centroids = update(
+            &centroids,
+            [(features, &predicted), (seeds, &seed_clusters)],
+        );
+
+        let next = assign(features, &centroids, distance);
+        let next_seeds = assign_seeds(seeds, seed_truth, &centroids, labels, distance);
+
+        if next == predicted && next_seeds == seed_clusters {
+            break;
+        }
+
+        predicted = next;
+        seed_clusters = next_seeds;
+    }
+
+    SeededFit {
+        centroids,
+        predicted,
+        iterations,
+    }
+}
+
+fn assign(
+    features: &Array2<f64>,
+    centroids: &Array2<f64>,
+    distance: &DistanceFunction,
+) -> Array1<usize> {
+    features
+        .axis_iter(Axis(0))
+        .map(|x| nearest_centroid(centroids.view(), distance, x))
+        .collect()
+}
+
+/// Nearest centroid among the clusters with the seed's label, the cannot-link
+/// half of the constraint.
+fn assign_seeds(
+    seeds: &Array2<f64>,
+    seed_truth: &[Label],
+    centroids: &Array2<f64>,
+    labels: &[Label],
+    distance: &DistanceFunction,
+) -> Array1<usize> {
+    seeds
+        .axis_iter(Axis(0))
+        .zip(seed_truth)
+        .map(|(x, truth)| {
+            (0..centroids.nrows())
+                .filter(|&c| labels[c] == *truth)
+                .map(|c| (c, distance.distance(x, centroids.row(c))))
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map_or(0, |(c, _)| c)
+        })
+        .collect()
+}
+
+/// Mean of each cluster's members, empty clusters stay where they were.
+fn update<'a>(
+    previous: &Array2<f64>,
+    groups: impl IntoIterator<Item = (&'a Array2<f64>, &'a Array1<usize>)>,
+) -> Array2<f64> {
+    let mut sums = Array2::<f64>::zeros(previous.dim());
+    let mut counts = vec![0usize; previous.nrows()];
+
+    for (rows, clusters) in groups {
+        for (x, &cluster) in rows.axis_iter(Axis(0)).zip(clusters) {
+            let mut sum = sums.row_mut(cluster);
+            sum += &x;
+            counts[cluster] += 1;
+        }
+    }
+
+    let mut centroids = previous.clone();
+    for (c, &count) in counts.iter().enumerate() {
+        if count > 0 {
+            centroids.row_mut(c).assign(&(&sums.row(c) / count as f64));
+        }
+    }
+
+    centroids
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::array;
+
+    use super::*;
+
+    #[test]
+    fn seeds_stay_with_their_label() {
+        let features = array![[0.], [0.1], [0.2], [10.], [10.1], [10.2]];
+        let labels = [Label::Human, Label::Ai];
+        // the last ai seed sits right next to the human cluster
+        let seeds = array![[0.], [0.1], [10.], [10.1], [0.5]];
+        let seed_truth = [Label::Human, Label::Human, Label::Ai, Label::Ai, Label::Ai];
+
+        let fit = fit_seeded(
+            &features,
+            &seeds,
+            &seed_truth,
+            array![[0.], [10.]].view(),
+            &labels,
+            &DistanceFunction::L2,
+            100,
+        );
+        assert_eq!(fit.predicted, array![0, 0, 0, 1, 1, 1]);
+
+        let stray = seeds.row(4);
+        let nearest = nearest_centroid(fit.centroids.view(), &DistanceFunction::L2, stray);
+        assert_eq!(labels[nearest], Label::Human);
+
+        let seed_clusters = assign_seeds(
+            &seeds,
+            &seed_truth,
+            &fit.centroids,
+            &labels,
+            &DistanceFunction::L2,
+        );
+        assert_eq!(seed_clusters, array![0, 0, 1, 1, 1]);
+
+        // and it still pulls the ai centroid its way
+        assert!(fit.centroids[[1, 0]] < 10.);
+    }
+}
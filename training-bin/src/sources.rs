@@ -0,0 +1,316 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use futures::future::BoxFuture;
+use tokio::fs;
+
+use crate::config::{Config, Source, Table};
+use crate::corpus::{Changes, Corpus, Kind, MIGRATED, Record};
+use crate::summer_of_making::{self, SummerOfMaking};
+
+/// Somewhere texts come from. Every source writes the same [`Record`]s, tagged
+/// with its name, into the corpus.
+pub trait DataSource {
+    /// Stored as `records.source`
+    fn name(&self) -> &str;
+
+    /// Brings this source's records in the corpus up to date. `full` asks
+    /// incremental sources for a complete pass.
+    fn sync<'a>(
+        &'a self,
+        corpus: &'a mut Corpus,
+        full: bool,
+    ) -> BoxFuture<'a, anyhow::Result<Changes>>;
+}
+
+/// `[[sources]]` in order, Summer of Making alone when there are none.
+pub fn from_config(config: &Config) -> anyhow::Result<Vec<Box<dyn DataSource>>> {
+    if config.sources.is_empty() {
+        return Ok(vec![Box::new(SummerOfMaking::new(config.fetch.clone()))]);
+    }
+
+    let mut names = HashSet::new();
+    let mut sources: Vec<Box<dyn DataSource>> = Vec::new();
+
+    for config_source in &config.sources {
+        let source: Box<dyn DataSource> = match config_source {
+            Source::Som => Box::new(SummerOfMaking::new(config.fetch.clone())),
+            Source::Dir(dir) => Box::new(Directory {
+                name: dir.name.clone(),
+                path: dir.path.clone(),
+            }),
+            Source::Jsonl(table) => Box::new(Jsonl(table.clone())),
+            Source::Csv(table) => Box::new(Csv(table.clone())),
+        };
+
+        if !matches!(config_source, Source::Som) && source.name() == summer_of_making::SOURCE {
+            anyhow::bail!(
+                "`{}` is reserved for Summer of Making",
+                summer_of_making::SOURCE
+            );
+        }
+        // the texts `migrate` imported from som.data
+        if source.name() == MIGRATED {
+            anyhow::bail!("`{MIGRATED}` is reserved for migrated texts");
+        }
+        if !names.insert(source.name().to_string()) {
+            anyhow::bail!("two sources are named `{}`", source.name());
+        }
+
+        sources.push(source);
+    }
+
+    Ok(sources)
+}
+
+/// Every `.md`, `.markdown` and `.txt` file under `path`, keyed by the path
+/// relative to it.
+pub struct Directory {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl DataSource for Directory {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn sync<'a>(
+        &'a self,
+        corpus: &'a mut Corpus,
+        _full: bool,
+    ) -> BoxFuture<'a, anyhow::Result<Changes>> {
+        Box::pin(async move {
+            let mut records = Vec::new();
+
+            for path in text_files(&self.path).await? {
+                let key = path
+                    .strip_prefix(&self.path)?
+                    .to_string_lossy()
+                    .replace('\\', "/");
+
+                records.push(document(&self.name, key, fs::read_to_string(&path).await?));
+            }
+
+            corpus.replace_source(&self.name, &records)
+        })
+    }
+}
+
+/// One JSON object per line, the text in the `text` field.
+pub struct Jsonl(pub Table);
+
+impl DataSource for Jsonl {
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn sync<'a>(
+        &'a self,
+        corpus: &'a mut Corpus,
+        _full: bool,
+    ) -> BoxFuture<'a, anyhow::Result<Changes>> {
+        Box::pin(async move {
+            let Table {
+                name,
+                path,
+                text,
+                id,
+            } = &self.0;
+            let file = fs::read_to_string(path).await?;
+            let mut records = Vec::new();
+            let mut keys = HashSet::new();
+
+            for (i, line) in file.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let at = || format!("{}:{}", path.display(), i + 1);
+                let row: serde_json::Value =
+                    serde_json::from_str(line).map_err(|err| anyhow::anyhow!("{}: {err}", at()))?;
+
+                let Some(body) = row.get(text).and_then(|value| value.as_str()) else {
+                    anyhow::bail!("{}: no `{text}` string", at());
+                };
+                let key = match id.as_ref().and_then(|id| row.get(id)) {
+                    Some(serde_json::Value::String(id)) => id.clone(),
+                    Some(id) => id.to_string(),
+                    None => (i + 1).to_string(),
+                };
+                if !keys.insert(key.clone()) {
+                    anyhow::bail!("{}: duplicate id `{key}`", at());
+                }
+
+                records.push(document(name, key, body.to_string()));
+            }
+
+            corpus.replace_source(name, &records)
+        })
+    }
+}
+
+/// A CSV file with a header row, the text in the `text` column.
+pub struct Csv(pub Table);
+
+impl DataSource for Csv {
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    fn sync<'a>(
+        &'a self,
+        corpus: &'a mut Corpus,
+        _full: bool,
+    ) -> BoxFuture<'a, anyhow::Result<Changes>> {
+        Box::pin(async move {
+            let Table {
+                name,
+                path,
+                text,
+                id,
+            } = &self.0;
+            let bytes = fs::read(path).await?;
+            let mut reader = csv::Reader::from_reader(bytes.as_slice());
+
+            let headers = reader.headers()?.clone();
+            let column = |column: &str| {
+                headers
+                    .iter()
+                    .position(|header| header == column)
+                    .ok_or_else(|| anyhow::anyhow!("{} has no `{column}` column", path.display()))
+            };
+            let text_column = column(text)?;
+            let id_column = id.as_deref().map(column).transpose()?;
+
+            let mut records = Vec::new();
+            let mut keys = HashSet::new();
+
+            for (i, row) in reader.records().enumerate() {
+                let row = row?;
+                let key = match id_column {
+                    Some(column) => row.get(column).unwrap_or_default().to_string(),
+                    None => (i + 1).to_string(),
+                };
+                if !keys.insert(key.clone()) {
+                    let line = row.position().map_or(i + 2, |at| at.line() as usize);
+                    anyhow::bail!("{}:{line}: duplicate id `{key}`", path.display());
+                }
+
+                records.push(document(
+                    name,
+                    key,
+                    row.get(text_column).unwrap_or_default().to_string(),
+                ));
+            }
+
+            corpus.replace_source(name, &records)
+        })
+    }
+}
+
+fn document(source: &str, key: String, text: String) -> Record {
+    Record {
+        source: source.to_string(),
+        key: Some(key),
+        kind: Some(Kind::Document),
+        text,
+        ..Default::default()
+    }
+}
+
+/// Sorted, so documents land in the corpus in the same order everywhere.
+async fn text_files(root: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let mut entries = fs::read_dir(&dir).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+
+            if entry.file_type().await?.is_dir() {
+                dirs.push(path);
+            } else if path
+                .extension()
+                .is_some_and(|ext| ext == "md" || ext == "markdown" || ext == "txt")
+            {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(file: &str, contents: &str) -> Table {
+        let path = std::env::temp_dir().join(format!("sonai-{}-{file}", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+
+        Table {
+            name: "test".into(),
+            path,
+            text: "text".into(),
+            id: Some("id".into()),
+        }
+    }
+
+    async fn sync(source: impl DataSource) -> anyhow::Result<Changes> {
+        let mut corpus = Corpus::open(Path::new(":memory:"))?;
+        source.sync(&mut corpus, false).await
+    }
+
+    #[tokio::test]
+    async fn jsonl_duplicate_ids() {
+        let table = table(
+            "duplicate.jsonl",
+            "{\"id\": 1, \"text\": \"a\"}\n\n{\"id\": 2, \"text\": \"b\"}\n{\"id\": 1, \"text\": \"c\"}\n",
+        );
+        let path = table.path.clone();
+
+        let err = sync(Jsonl(table)).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("{}:4: duplicate id `1`", path.display())
+        );
+    }
+
+    #[tokio::test]
+    async fn csv_duplicate_ids() {
+        let table = table("duplicate.csv", "id,text\na,one\nb,two\na,three\n");
+        let path = table.path.clone();
+
+        let err = sync(Csv(table)).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("{}:4: duplicate id `a`", path.display())
+        );
+    }
+
+    #[tokio::test]
+    async fn distinct_ids_sync() {
+        let changes = sync(Csv(table("distinct.csv", "id,text\na,one\nb,two\n")))
+            .await
+            .unwrap();
+        assert_eq!(changes.added, 2);
+    }
+
+    #[test]
+    fn reserved_names() {
+        for name in ["som", "migrated"] {
+            let config = Config::parse(format!(
+                "[[sources]]\ntype = \"dir\"\nname = \"{name}\"\npath = \"notes\"\n"
+            ))
+            .unwrap();
+            let err = from_config(&config).err().unwrap();
+            assert!(err.to_string().contains("is reserved"), "{err}");
+        }
+    }
+}
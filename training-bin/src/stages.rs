@@ -0,0 +1,927 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use bincode::config::standard;
+use bincode::serde::{decode_from_slice, encode_to_vec};
+use colored::Colorize;
+use linfa::traits::Predict;
+use ndarray::{Array1, Array2, Axis};
+use num_format::{Locale, ToFormattedString};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use sonai_metrics::{
+    Clusterer, DistanceFunction, FeatureSet, GaussianMixture, Label, Model, NgramModel, NgramUnit,
+    Pca, Scaler, TextMetricFactory, TextMetrics, features_from_metrics_with,
+};
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use tokio::fs;
+
+use crate::config::{ClusterCount, Config};
+use crate::corpus::{Corpus, Record};
+use crate::embeddings::{embed_corpus, embed_with};
+use crate::labeling::{composite_labels, composite_scores, seed_labels, seed_recall};
+use crate::manifest::{self, Inputs, Manifest, ModelSummary};
+use crate::report::{choose_k, compare_distances, print_moved, silhouette};
+use crate::search::{Algorithm, SearchData, results_table, search};
+use crate::seeded::fit_seeded;
+use crate::sources;
+use crate::{fit_kmeans, read_texts};
+
+/// Written by `metrics`, everything `train` needs without touching the texts.
+#[derive(Serialize, serde::Deserialize)]
+pub struct MetricsFile {
+    /// Layout of `corpus` and the seeds
+    pub features: FeatureSet,
+    /// Registry metrics leading each row, in column order
+    pub metrics: Vec<String>,
+    /// Registry scales, one per metric
+    pub scales: Vec<f64>,
+    pub language_model: Option<NgramModel>,
+    pub pca: Option<Pca>,
+    /// Unscaled, one row per text in the data file
+    pub corpus: Array2<f64>,
+    pub seeds: Option<(Array2<f64>, Vec<Label>)>,
+    /// Of the corpus texts, see [`corpus_sha256`]
+    pub corpus_sha256: String,
+    pub inputs: Inputs,
+}
+
+pub async fn fetch(config: &Config, full: bool) -> anyhow::Result<()> {
+    let sources = sources::from_config(config)?;
+    let mut corpus = Corpus::open(&config.paths.corpus)?;
+
+    for source in &sources {
+        println!("Syncing {}", source.name());
+
+        let changes = source.sync(&mut corpus, full).await?;
+        println!(
+            "{}: {} added, {} updated, {} deleted",
+            source.name(),
+            changes.added,
+            changes.updated,
+            changes.deleted
+        );
+    }
+
+    println!(
+        "{} holds {} records",
+        config.paths.corpus.display(),
+        corpus.len()?
+    );
+
+    Ok(())
+}
+
+/// Imports the texts of the old bincode `som.data` into the corpus.
+pub async fn migrate(config: &Config) -> anyhow::Result<()> {
+    let texts: Vec<String> = read(&config.paths.data, "fetch").await?;
+
+    let imported = Corpus::open(&config.paths.corpus)?.import_legacy(texts)?;
+    println!(
+        "Imported {imported} texts from {} into {}",
+        config.paths.data.display(),
+        config.paths.corpus.display()
+    );
+
+    Ok(())
+}
+
+pub async fn metrics(config: &Config) -> anyhow::Result<()> {
+    let paths = &config.paths;
+    let data = open_corpus(&paths.corpus).await?.texts()?;
+
+    let inputs = Inputs {
+        lm_sha256: match &paths.lm {
+            Some(dir) => Some(manifest::sha256_dir(dir).await?),
+            None => None,
+        },
+        seeds_sha256: match &paths.seeds {
+            Some(dir) => Some(manifest::sha256_dir(dir).await?),
+            None => None,
+        },
+    };
+
+    let language_model = match &paths.lm {
+        Some(dir) => {
+            println!("Fitting language model on {}", dir.display());
+            let texts = read_texts(dir).await?;
+
+            Some(NgramModel::fit(&texts, 4, NgramUnit::Char))
+        }
+        None => None,
+    };
+
+    let feature_set = config
+        .features
+        .feature_set(language_model.is_some(), paths.embeddings.is_some());
+
+    println!("Calculating metrics");
+    let mut factory = TextMetricFactory::new()?.with_features(feature_set.clone());
+    if let Some(language_model) = &language_model {
+        factory = factory.with_language_model(language_model.clone());
+    }
+
+    let mut metrics: Vec<TextMetrics> = factory
+        .calculate_iter(&data)
+        .collect::<Result<_, _>>()
+        .map_err(anyhow::Error::msg)?;
+
+    let pca = match &paths.embeddings {
+        Some(dir) => {
+            println!("Embedding");
+            Some(embed_corpus(
+                dir,
+                &data,
+                &mut metrics,
+                feature_set.embeddings,
+            )?)
+        }
+        None => None,
+    };
+
+    let metrics_refs: Vec<&TextMetrics> = metrics.iter().collect();
+    let corpus = features_from_metrics_with(&metrics_refs, &feature_set);
+
+    let seeds = match &paths.seeds {
+        Some(dir) => {
+            println!("Calculating seed metrics from {}", dir.display());
+
+            let ai_seeds = read_texts(&dir.join("ai")).await?;
+            let human_seeds = read_texts(&dir.join("human")).await?;
+
+            let seeds: Vec<String> = ai_seeds.iter().chain(&human_seeds).cloned().collect();
+            let seed_truth: Vec<Label> = std::iter::repeat_n(Label::Ai, ai_seeds.len())
+                .chain(std::iter::repeat_n(Label::Human, human_seeds.len()))
+                .collect();
+
+            let mut seed_metrics: Vec<TextMetrics> = factory
+                .calculate_iter(&seeds)
+                .collect::<Result<_, _>>()
+                .map_err(anyhow::Error::msg)?;
+            if let (Some(dir), Some(pca)) = (&paths.embeddings, &pca) {
+                embed_with(dir, &seeds, &mut seed_metrics, pca)?;
+            }
+
+            let seed_refs: Vec<&TextMetrics> = seed_metrics.iter().collect();
+
+            Some((
+                features_from_metrics_with(&seed_refs, &feature_set),
+                seed_truth,
+            ))
+        }
+        None => None,
+    };
+
+    let file = MetricsFile {
+        features: feature_set,
+        metrics: factory.registry().names().map(String::from).collect(),
+        scales: factory.registry().scales().collect(),
+        language_model,
+        pca,
+        corpus,
+        seeds,
+        corpus_sha256: corpus_sha256(&data)?,
+        inputs,
+    };
+
+    write(&paths.metrics, &file).await?;
+    println!(
+        "Wrote {}x{} features to {}",
+        file.corpus.nrows(),
+        file.corpus.ncols(),
+        paths.metrics.display()
+    );
+
+    Ok(())
+}
+
+pub async fn train(config: &Config) -> anyhow::Result<()> {
+    let train = &config.train;
+    let params = train.kmeans_params();
+    let metrics: MetricsFile = read(&config.paths.metrics, "metrics").await?;
+
+    if train.semi_supervised && metrics.seeds.is_none() {
+        anyhow::bail!("semi_supervised needs paths.seeds, rerun `metrics` with seeds");
+    }
+    if train.semi_supervised && train.algorithm != Algorithm::KMeans {
+        anyhow::bail!("semi_supervised only constrains KMeans");
+    }
+    if train.semi_supervised && train.search.is_some() {
+        anyhow::bail!("search scores unconstrained fits, drop semi_supervised");
+    }
+
+    let mut feature_set = metrics.features.clone();
+    let mut scaler_kind = train.scaler;
+    let mut distance_kind = train.distance;
+    let mut algorithm = train.algorithm;
+    let mut k = match train.k {
+        ClusterCount::Fixed(k) => Some(k),
+        ClusterCount::Auto(_) => None,
+    };
+
+    if let Some(strategy) = train.search {
+        let data = SearchData {
+            corpus: &metrics.corpus,
+            features: &metrics.features,
+            seeds: metrics
+                .seeds
+                .as_ref()
+                .map(|(features, truth)| (features, truth.as_slice())),
+            scales: &metrics.scales,
+        };
+
+        let results = search(&data, strategy, train.folds, &params)?;
+        let table = results_table(&results);
+
+        fs::write(&config.paths.search, &table).await?;
+        println!(
+            "\n{}",
+            format!("Search results ({})", config.paths.search.display())
+                .bold()
+                .cyan()
+        );
+        for line in table.lines().take(11) {
+            println!("{line}");
+        }
+
+        let Some(best) = results.into_iter().next() else {
+            anyhow::bail!("every search candidate failed to fit");
+        };
+        println!("best: {}", best.candidate);
+
+        let best = best.candidate;
+        k = Some(best.k);
+        distance_kind = best.distance;
+        scaler_kind = best.scaler;
+        algorithm = best.algorithm;
+        feature_set = best.features;
+    }
+
+    let n_metrics = metrics.scales.len();
+    let columns = metrics.features.columns_of(&feature_set, n_metrics);
+    let features = metrics.corpus.select(Axis(1), &columns);
+
+    let weights: Vec<f64> = metrics
+        .scales
+        .iter()
+        .copied()
+        .chain(std::iter::repeat_n(1., feature_set.n_block_features()))
+        .collect();
+
+    let scaler = Scaler::fit(scaler_kind, features.view(), &weights);
+    let features = scaler.transform(features.view());
+
+    let distance = DistanceFunction::fit(distance_kind, features.view());
+
+    let k = match k {
+        Some(k) => k,
+        None => choose_k(&features, &distance, 2..=8, &params)?,
+    };
+
+    println!("Training");
+    let model = fit_kmeans(&features, k, distance.clone(), &params)?;
+
+    println!("Predicting");
+    let mut predicted: Array1<usize> = model.predict(&features);
+
+    let mut clusterer = match algorithm {
+        Algorithm::Gmm(covariance) => {
+            println!("Fitting {covariance:?} mixture");
+            let gmm = GaussianMixture::fit(features.view(), &predicted, covariance, 1000, 1e-6);
+            predicted = gmm.predict(features.view());
+
+            Clusterer::Gmm(gmm)
+        }
+        Algorithm::KMeans => Clusterer::KMeans {
+            kmeans: model,
+            distance,
+        },
+    };
+
+    let composite = composite_scores(&features, n_metrics);
+    let mut labels = composite_labels(&composite, &predicted, k);
+
+    let seeds = match &metrics.seeds {
+        Some((seed_features, seed_truth)) => {
+            let seed_features = scaler.transform(seed_features.select(Axis(1), &columns).view());
+
+            labels = seed_labels(
+                &labels,
+                &clusterer.predict(seed_features.view()),
+                seed_truth,
+            )?;
+
+            Some((seed_features, seed_truth))
+        }
+        None => {
+            println!(
+                "{}",
+                "No seeds, labeling clusters by composite score alone".yellow()
+            );
+
+            None
+        }
+    };
+
+    if train.semi_supervised
+        && let Some((seed_features, seed_truth)) = &seeds
+        && let Clusterer::KMeans { kmeans, distance } = &clusterer
+    {
+        println!("Fitting seeded clusters");
+
+        let distance = distance.clone();
+        let fit = fit_seeded(
+            &features,
+            seed_features,
+            seed_truth,
+            kmeans.centroids().view(),
+            &labels,
+            &distance,
+            params.max_iterations as usize,
+        );
+
+        println!("Converged after {} iterations", fit.iterations);
+        print_moved(&predicted, &fit.predicted, &labels);
+
+        predicted = fit.predicted;
+        clusterer = Clusterer::Seeded {
+            centroids: fit.centroids,
+            distance,
+        };
+    }
+
+    print_split(&predicted, &labels);
+
+    let mut cluster_sizes = vec![0; labels.len()];
+    for &cluster in &predicted {
+        cluster_sizes[cluster] += 1;
+    }
+
+    let model = Model {
+        metrics: metrics.metrics.clone(),
+        features: feature_set,
+        scaler,
+        language_model: metrics.language_model,
+        pca: metrics.pca,
+        clusterer,
+        labels,
+    };
+
+    let model_bytes = encode_to_vec(&model, standard())?;
+    fs::write(&config.paths.model, &model_bytes).await?;
+    println!("Wrote {}", config.paths.model.display());
+
+    let (git_commit, git_dirty) = manifest::git_state();
+    let lockfile = Path::new(env!("CARGO_MANIFEST_DIR")).join("../Cargo.lock");
+
+    let run = Manifest {
+        created: OffsetDateTime::now_utc().format(&Rfc3339)?,
+        training_version: env!("CARGO_PKG_VERSION").to_string(),
+        sonai_metrics_version: sonai_metrics::VERSION.to_string(),
+        git_commit,
+        git_dirty,
+        lockfile_sha256: fs::read(lockfile)
+            .await
+            .ok()
+            .map(|bytes| manifest::sha256(&bytes)),
+        lexicon_sha256: manifest::lexicon_sha256(),
+        seed: train.seed,
+        config: config.source.clone(),
+        corpus: manifest::Corpus {
+            sha256: metrics.corpus_sha256,
+            records: metrics.corpus.nrows(),
+        },
+        inputs: metrics.inputs,
+        model: ModelSummary {
+            sha256: manifest::sha256(&model_bytes),
+            labels: model.labels.clone(),
+            cluster_sizes,
+            centers: model
+                .clusterer
+                .centers()
+                .rows()
+                .into_iter()
+                .map(|row| row.to_vec())
+                .collect(),
+        },
+    };
+
+    run.write(&config.paths.manifest).await?;
+    println!("Wrote {}", config.paths.manifest.display());
+
+    Ok(())
+}
+
+pub async fn evaluate(config: &Config) -> anyhow::Result<()> {
+    let metrics: MetricsFile = read(&config.paths.metrics, "metrics").await?;
+    let model: Model = read(&config.paths.model, "train").await?;
+
+    let columns = model_columns(&model, &metrics)?;
+    let features = corpus_features(&model, &metrics, &columns);
+    let predicted = model.clusterer.predict(features.view());
+
+    println!("\n{}", "Clusters".bold().cyan());
+    print_split(&predicted, &model.labels);
+
+    // the mixture isn't distance based, score its clusters in plain L2
+    let distance = match &model.clusterer {
+        Clusterer::KMeans { distance, .. } | Clusterer::Seeded { distance, .. } => distance.clone(),
+        Clusterer::Gmm(_) => DistanceFunction::L2,
+    };
+    println!(
+        "silhouette={:.3}",
+        silhouette(&features, &predicted, &distance)
+    );
+
+    if let Some((seed_features, seed_truth)) = &metrics.seeds {
+        let seed_features = model
+            .scaler
+            .transform(seed_features.select(Axis(1), &columns).view());
+        let seed_clusters = model.clusterer.predict(seed_features.view());
+
+        println!(
+            "seed recall: ai={:.2} human={:.2}",
+            seed_recall(&model.labels, &seed_clusters, seed_truth, Label::Ai),
+            seed_recall(&model.labels, &seed_clusters, seed_truth, Label::Human),
+        );
+    }
+
+    if config.evaluate.compare_distances {
+        let composite = composite_scores(&features, metrics.scales.len());
+        let is_ai = predicted.mapv(|cluster| model.labels[cluster] == Label::Ai);
+
+        compare_distances(
+            &features,
+            model.clusterer.n_clusters(),
+            &composite,
+            &is_ai,
+            &config.train.kmeans_params(),
+        )?;
+    }
+
+    Ok(())
+}
+
+pub async fn report(config: &Config) -> anyhow::Result<()> {
+    let records = open_corpus(&config.paths.corpus).await?.records()?;
+    let metrics: MetricsFile = read(&config.paths.metrics, "metrics").await?;
+    let model: Model = read(&config.paths.model, "train").await?;
+
+    // the predictions are joined to the records by position
+    let texts: Vec<String> = records.iter().map(|record| record.text.clone()).collect();
+    if corpus_sha256(&texts)? != metrics.corpus_sha256 {
+        anyhow::bail!(
+            "{} changed since `metrics` ran, rerun `metrics` and `train`",
+            config.paths.corpus.display()
+        );
+    }
+
+    let columns = model_columns(&model, &metrics)?;
+    let predicted = model
+        .clusterer
+        .predict(corpus_features(&model, &metrics, &columns).view());
+
+    let mut factory = TextMetricFactory::new()?.with_features(model.features.clone());
+    if let Some(language_model) = &model.language_model {
+        factory = factory.with_language_model(language_model.clone());
+    }
+
+    let mut clusters: BTreeMap<usize, Vec<&Record>> = BTreeMap::new();
+    for (&cluster, record) in predicted.iter().zip(&records) {
+        clusters.entry(cluster).or_default().push(record);
+    }
+
+    // same samples every time for the same model
+    let mut rng = StdRng::seed_from_u64(config.train.seed);
+
+    for (cluster, items) in clusters {
+        println!(
+            "\n{}",
+            format!(
+                "==================== Cluster {cluster} ({:?}) ====================",
+                model.labels[cluster]
+            )
+            .bold()
+            .cyan()
+        );
+
+        let sample = items.choose_multiple(&mut rng, 5);
+
+        for (i, record) in sample.into_iter().enumerate() {
+            println!(
+                "{}",
+                format!("--- Sample {i} ({}) ---", record.origin())
+                    .bold()
+                    .yellow()
+            );
+            println!(
+                "{} {}",
+                "Features:".green(),
+                factory
+                    .calculate(&record.text)
+                    .map_err(anyhow::Error::msg)?
+            );
+            println!("{}\n{}", "Text:".blue(), record.text);
+            println!("{}", "-------------------------------\n".dimmed());
+        }
+    }
+
+    let ai = predicted
+        .iter()
+        .filter(|&&cluster| model.labels[cluster] == Label::Ai)
+        .count();
+    let human = predicted.len() - ai;
+    let total = (ai + human).max(1) as f64;
+
+    let human_pct = (human as f64) * 100. / total;
+    let ai_pct = (ai as f64) * 100. / total;
+
+    // "Oct 19, 2026"
+    let today = OffsetDateTime::now_utc();
+    let month = today.month().to_string();
+    let date = format!("{} {}, {}", &month[..3], today.day(), today.year());
+
+    let html = index_html(
+        &date,
+        &human.to_formatted_string(&Locale::en),
+        &ai.to_formatted_string(&Locale::en),
+        human_pct,
+        ai_pct,
+    );
+
+    fs::write(&config.paths.html, html).await?;
+    println!("Wrote {}", config.paths.html.display());
+
+    Ok(())
+}
+
+/// Copies the trained model to where `sonai` embeds it, only this stage
+/// touches the committed model.
+pub async fn export(config: &Config) -> anyhow::Result<()> {
+    let model: Model = read(&config.paths.model, "train").await?;
+
+    write(&config.paths.export, &model).await?;
+    println!(
+        "Exported {} to {}",
+        config.paths.model.display(),
+        config.paths.export.display()
+    );
+
+    Ok(())
+}
+
+/// Reruns `metrics` and `train` from a manifest into a scratch directory and
+/// checks the model comes out byte for byte the same.
+pub async fn verify(config: &Config, manifest_path: Option<PathBuf>) -> anyhow::Result<()> {
+    let manifest_path = manifest_path.unwrap_or_else(|| config.paths.manifest.clone());
+    let expected = Manifest::read(&manifest_path).await?;
+
+    let mut rerun = Config::parse(expected.config.clone())?;
+
+    let texts = open_corpus(&rerun.paths.corpus).await?.texts()?;
+    if corpus_sha256(&texts)? != expected.corpus.sha256 {
+        anyhow::bail!(
+            "{} changed since the run, the manifest expects sha256 {}",
+            rerun.paths.corpus.display(),
+            expected.corpus.sha256
+        );
+    }
+
+    // either one changes the features, the rerun can't match
+    let commit = expected.git_commit.as_deref().unwrap_or("the run's commit");
+    if expected.lexicon_sha256 != manifest::lexicon_sha256() {
+        anyhow::bail!(
+            "the built-in lexicons changed since the run, check out {commit} to verify it"
+        );
+    }
+    if expected.sonai_metrics_version != sonai_metrics::VERSION {
+        anyhow::bail!(
+            "the run used sonai_metrics {}, this is {}, check out {commit} to verify it",
+            expected.sonai_metrics_version,
+            sonai_metrics::VERSION
+        );
+    }
+
+    let scratch = std::env::temp_dir().join(format!("sonai-verify-{}", std::process::id()));
+    fs::create_dir_all(&scratch).await?;
+
+    rerun.paths.metrics = scratch.join("metrics.bin");
+    rerun.paths.model = scratch.join("model.sonai");
+    rerun.paths.manifest = scratch.join("manifest.toml");
+    rerun.paths.search = scratch.join("search.tsv");
+
+    let result = async {
+        metrics(&rerun).await?;
+        train(&rerun).await?;
+        Manifest::read(&rerun.paths.manifest).await
+    }
+    .await;
+    fs::remove_dir_all(&scratch).await?;
+    let actual = result?;
+
+    if actual.inputs.lm_sha256 != expected.inputs.lm_sha256
+        || actual.inputs.seeds_sha256 != expected.inputs.seeds_sha256
+    {
+        println!(
+            "{}",
+            "warning: lm or seed texts changed since the run".yellow()
+        );
+    }
+
+    if actual.model.sha256 == expected.model.sha256 {
+        println!(
+            "{}",
+            format!("Reproduced {} exactly", manifest_path.display())
+                .bold()
+                .green()
+        );
+
+        return Ok(());
+    }
+
+    println!(
+        "labels: {:?} -> {:?}",
+        expected.model.labels, actual.model.labels
+    );
+    println!(
+        "cluster sizes: {:?} -> {:?}",
+        expected.model.cluster_sizes, actual.model.cluster_sizes
+    );
+
+    anyhow::bail!(
+        "model differs, sha256 {} -> {}",
+        expected.model.sha256,
+        actual.model.sha256
+    )
+}
+
+/// Where the model's columns sit in the metrics file rows.
+fn model_columns(model: &Model, metrics: &MetricsFile) -> anyhow::Result<Vec<usize>> {
+    let names: Vec<&str> = metrics.metrics.iter().map(String::as_str).collect();
+
+    model
+        .columns(&names, &metrics.features)
+        .map_err(|err| anyhow::anyhow!("rerun `metrics` and `train`, {err}"))
+}
+
+/// Corpus rows in the model's layout, scaled like inference does.
+fn corpus_features(model: &Model, metrics: &MetricsFile, columns: &[usize]) -> Array2<f64> {
+    model
+        .scaler
+        .transform(metrics.corpus.select(Axis(1), columns).view())
+}
+
+fn print_split(predicted: &Array1<usize>, labels: &[Label]) {
+    let mut sizes = vec![0usize; labels.len()];
+    for &cluster in predicted {
+        sizes[cluster] += 1;
+    }
+
+    for (cluster, (size, label)) in sizes.iter().zip(labels).enumerate() {
+        println!("cluster {cluster} ({label:?}): {size}");
+    }
+
+    let ai: usize = sizes
+        .iter()
+        .zip(labels)
+        .filter(|(_, label)| **label == Label::Ai)
+        .map(|(size, _)| size)
+        .sum();
+    let total = predicted.len().max(1) as f64;
+
+    println!(
+        "labels={labels:?} human=({:.2}%, {}) ai=({:.2}%, {ai})",
+        (predicted.len() - ai) as f64 * 100. / total,
+        predicted.len() - ai,
+        ai as f64 * 100. / total,
+    );
+}
+
+async fn open_corpus(path: &Path) -> anyhow::Result<Corpus> {
+    if !fs::try_exists(path).await? {
+        anyhow::bail!(
+            "{} is missing, run `fetch` (or `migrate` for an old som.data) first",
+            path.display()
+        );
+    }
+
+    Corpus::open(path)
+}
+
+/// Hash of the texts as the old `som.data` stored them, so manifests written
+/// before the corpus moved to SQLite still verify.
+fn corpus_sha256(texts: &[String]) -> anyhow::Result<String> {
+    Ok(manifest::sha256(&encode_to_vec(texts, standard())?))
+}
+
+async fn read<T: DeserializeOwned>(path: &Path, stage: &str) -> anyhow::Result<T> {
+    let bytes = read_bytes(path, stage).await?;
+
+    Ok(decode_from_slice(&bytes, standard())?.0)
+}
+
+async fn read_bytes(path: &Path, stage: &str) -> anyhow::Result<Vec<u8>> {
+    if !fs::try_exists(path).await? {
+        anyhow::bail!("{} is missing, run `{stage}` first", path.display());
+    }
+
+    Ok(fs::read(path).await?)
+}
+
+async fn write<T: Serialize>(path: &Path, value: &T) -> anyhow::Result<()> {
+    fs::write(path, encode_to_vec(value, standard())?).await?;
+
+    Ok(())
+}
+
+fn index_html(date: &str, human: &str, ai: &str, human_pct: f64, ai_pct: f64) -> String {
+    format!(
+        r##"<!-- Do not change this file manually, please edit the template string at the bottom of training-bin/src/stages.rs and rebuild  -->
+<!doctype html>
+<html lang="en" class="dark">
+  <head>
+    <meta charset="UTF-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1.0" />
+    <title>How much of SoM is AI?</title>
+  </head>
+  <body
+    class="bg-gray-100 text-gray-800 dark:bg-gray-900 dark:text-gray-100 antialiased">
+    <header class="bg-white dark:bg-gray-800 shadow-md">
+      <div class="max-w-5xl mx-auto py-6 px-5 flex items-center justify-between">
+        <h1 class="text-3xl font-semibold">sonai Detector Demo</h1>
+        <a
+          href="https://github.com/elijah629/how-much-of-som-is-ai"
+          target="_blank"
+          class="text-blue-600 underline hover:text-blue-800 dark:text-blue-400 dark:hover:text-blue-300"
+          >Source</a
+        >
+      </div>
+    </header>
+    <main class="max-w-5xl mx-auto p-6 space-y-8">
+      <section class="bg-white dark:bg-gray-800 rounded-lg shadow-sm p-6">
+        <h2 class="text-2xl font-medium mb-4">
+          Projects + Devlog stats as of
+          <span class="font-semibold">{date}</span>:
+        </h2>
+        <div class="flex flex-wrap gap-4 text-lg">
+          <div class="flex items-center space-x-2">
+            <span class="font-semibold">Human:</span>
+            <span class="text-green-600 dark:text-green-400">{human}</span>
+          </div>
+          <div class="flex items-center space-x-2">
+            <span class="font-semibold">AI:</span>
+            <span class="text-blue-600 dark:text-blue-400">{ai}</span>
+          </div>
+          <div class="flex items-center space-x-2">
+            <span class="font-semibold">Human %:</span>
+            <span class="text-green-600 dark:text-green-400">{human_pct:.2}%</span>
+          </div>
+          <div class="flex items-center space-x-2">
+            <span class="font-semibold">AI %:</span>
+            <span class="text-blue-600 dark:text-blue-400">{ai_pct:.2}%</span>
+          </div>
+        </div>
+      </section>
+
+      <section class="bg-white dark:bg-gray-800 rounded-lg shadow-sm p-6">
+        <h2 class="text-2xl font-medium mb-4">Check Your Own Devlogs!</h2>
+        <div class="flex flex-col md:flex-row gap-4">
+          <textarea
+            id="input"
+            class="flex-1 resize-y grow min-h-[500px] p-4 border border-gray-300 dark:border-gray-600 rounded-lg bg-white dark:bg-gray-900 focus:outline-none focus:ring-2 focus:ring-blue-500 dark:focus:ring-blue-400 scrollbar-hide"
+            placeholder="Type here, AI checks live (The model is very fast and runs entirely in your browser with WASM!)"
+          ></textarea>
+          <pre
+            id="output"
+            class="w-min p-4 border border-gray-300 dark:border-gray-600 rounded-lg bg-gray-50 dark:bg-gray-900 min-w-sm"
+          ></pre>
+        </div>
+      </section>
+    </main>
+    <script type="module" src="/src/main.ts"></script>
+  </body>
+</html>"##
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::corpus::Kind;
+
+    /// A config writing everything under a fresh temp dir, with a small
+    /// corpus of marketing copy and terse notes in it.
+    fn run(name: &str) -> Config {
+        let dir = std::env::temp_dir().join(format!("sonai-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = |file: &str| dir.join(file).display().to_string();
+        let config = Config::parse(format!(
+            "[paths]\ncorpus = {:?}\nmetrics = {:?}\nmodel = {:?}\nmanifest = {:?}\nsearch = {:?}\nhtml = {:?}\n",
+            path("corpus.db"),
+            path("metrics.bin"),
+            path("model.sonai"),
+            path("manifest.toml"),
+            path("search.tsv"),
+            path("report.html"),
+        ))
+        .unwrap();
+
+        let texts = (0..12)
+            .flat_map(|i| {
+                [
+                    format!("✨ Day {i}: this modern, user-friendly interface delivers real-time — seamless engagement! ✨"),
+                    format!("fixed bug {i}, parser was slow. still broken on windows"),
+                ]
+            })
+            .collect();
+        Corpus::open(&config.paths.corpus)
+            .unwrap()
+            .import_legacy(texts)
+            .unwrap();
+
+        config
+    }
+
+    #[tokio::test]
+    async fn report_refuses_a_changed_corpus() {
+        let config = run("report");
+        metrics(&config).await.unwrap();
+        train(&config).await.unwrap();
+
+        report(&config).await.unwrap();
+
+        let note = Record {
+            source: "notes".into(),
+            key: Some("a.txt".into()),
+            kind: Some(Kind::Document),
+            text: "a text added after `metrics`".into(),
+            ..Default::default()
+        };
+        Corpus::open(&config.paths.corpus)
+            .unwrap()
+            .replace_source("notes", &[note])
+            .unwrap();
+
+        let err = report(&config).await.unwrap_err();
+        assert!(err.to_string().contains("rerun `metrics`"), "{err}");
+    }
+
+    type Edit = fn(&mut Manifest);
+
+    async fn tamper(config: &Config, edit: impl FnOnce(&mut Manifest)) {
+        let mut manifest = Manifest::read(&config.paths.manifest).await.unwrap();
+        edit(&mut manifest);
+        manifest.write(&config.paths.manifest).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn verify_catches_tampered_manifests() {
+        let config = run("verify");
+        metrics(&config).await.unwrap();
+        train(&config).await.unwrap();
+
+        verify(&config, None).await.unwrap();
+
+        let untouched = Manifest::read(&config.paths.manifest).await.unwrap();
+        let restore = |manifest: &mut Manifest| {
+            manifest.lexicon_sha256 = untouched.lexicon_sha256.clone();
+            manifest.sonai_metrics_version = untouched.sonai_metrics_version.clone();
+            manifest.corpus.sha256 = untouched.corpus.sha256.clone();
+            manifest.model.sha256 = untouched.model.sha256.clone();
+        };
+
+        let cases: [(&str, Edit); 4] = [
+            ("model differs", |manifest| {
+                manifest.model.sha256 = "0".repeat(64)
+            }),
+            ("lexicons changed", |manifest| {
+                manifest.lexicon_sha256 = "0".repeat(64)
+            }),
+            ("sonai_metrics 0.0.0", |manifest| {
+                manifest.sonai_metrics_version = "0.0.0".to_string()
+            }),
+            ("corpus.db changed", |manifest| {
+                manifest.corpus.sha256 = "0".repeat(64)
+            }),
+        ];
+
+        for (expected, edit) in cases {
+            tamper(&config, |manifest| {
+                restore(manifest);
+                edit(manifest);
+            })
+            .await;
+
+            let err = verify(&config, None).await.unwrap_err().to_string();
+            assert!(err.contains(expected), "{expected}: {err}");
+        }
+
+        std::fs::remove_dir_all(config.paths.corpus.parent().unwrap()).unwrap();
+    }
+}
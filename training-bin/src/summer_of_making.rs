@@ -1,10 +1,17 @@
-use std::time::Duration;
-
-use anyhow::{Result, anyhow};
-use futures::{StreamExt, TryStreamExt};
+use anyhow::Result;
+use futures::future::BoxFuture;
+use futures::{Stream, StreamExt, TryStreamExt};
 use reqwest::header::{COOKIE, HeaderMap, HeaderValue};
 use serde::Deserialize;
 
+use crate::config::Fetch;
+use crate::corpus::{Changes, Corpus, Kind, Record, Sweep};
+use crate::http::{Fetcher, PageError};
+use crate::sources::DataSource;
+
+/// `records.source` of everything from the API, and of the migrated texts.
+pub const SOURCE: &str = "som";
+
 #[derive(Deserialize)]
 pub struct Devlogs {
     devlogs: Vec<Devlog>,
@@ -17,29 +24,68 @@ pub struct Projects {
     pagination: Pagination,
 }
 
+#[derive(Deserialize)]
+pub struct Comments {
+    comments: Vec<Comment>,
+    pagination: Pagination,
+}
+
+#[derive(Deserialize)]
+pub struct Users {
+    users: Vec<User>,
+    pagination: Pagination,
+}
+
+// unknown fields are ignored and anything but the id may be missing, the API
+// adds and drops fields now and then
+
 #[derive(Deserialize)]
 pub struct Devlog {
-    //id: u16,
+    pub id: u32,
+    #[serde(default)]
     pub text: String,
-    ////  attachment: Option<Attachment>,
-    // project_id: u16,
-    // slack_id: String,
-    // created_at: String,
-    // updated_at: String,
+    /// Kept as JSON, its shape isn't documented
+    pub attachment: Option<serde_json::Value>,
+    pub project_id: Option<u32>,
+    pub slack_id: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
 }
 
 #[derive(Deserialize)]
 pub struct Project {
-    //id: u16
-    // title: string
+    pub id: u32,
+    pub title: Option<String>,
+    #[serde(default)]
     pub description: String,
-    // category: null
-    // readme_link
-    // demo_link
-    // repo_link
-    // slack_id
-    // created_at
-    // updated_at
+    pub category: Option<String>,
+    pub readme_link: Option<String>,
+    pub demo_link: Option<String>,
+    pub repo_link: Option<String>,
+    pub slack_id: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct Comment {
+    pub id: u32,
+    #[serde(default, alias = "content")]
+    pub text: String,
+    pub devlog_id: Option<u32>,
+    pub slack_id: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct User {
+    pub id: u32,
+    pub slack_id: Option<String>,
+    pub display_name: Option<String>,
+    pub bio: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
 }
 
 pub trait PagintatedResponse<T> {
@@ -67,114 +113,691 @@ impl PagintatedResponse<Project> for Projects {
     }
 }
 
+impl PagintatedResponse<Comment> for Comments {
+    fn pagintation(&self) -> &Pagination {
+        &self.pagination
+    }
+
+    fn page(self) -> Vec<Comment> {
+        self.comments
+    }
+}
+
+impl PagintatedResponse<User> for Users {
+    fn pagintation(&self) -> &Pagination {
+        &self.pagination
+    }
+
+    fn page(self) -> Vec<User> {
+        self.users
+    }
+}
+
 #[derive(Deserialize)]
 pub struct Pagination {
     // page: u16,
     pages: u16,
-    count: u16,
+    // count: u16,
     // items: u16,
 }
 
-pub async fn pagintated_fetch<
-    T: for<'a> Deserialize<'a> + PagintatedResponse<D>,
-    D: for<'a> Deserialize<'a>,
->(
-    api_url: &str,
-    api_key: &str,
-) -> Result<Vec<D>> {
+pub fn fetcher(settings: &Fetch, api_key: &str) -> Result<Fetcher> {
     let mut headers = HeaderMap::new();
     headers.insert(
         COOKIE,
         HeaderValue::from_str(format!("_journey_session={api_key}").as_str())?,
     );
 
-    let client = reqwest::Client::builder()
-        .default_headers(headers)
-        .build()?;
+    Fetcher::new(settings, headers)
+}
+
+/// Brings the `D::KINDS` records in the corpus up to date with `api_url`, one
+/// committed page at a time so an interrupted sync resumes.
+///
+/// A full sweep reads every page and deletes what it didn't see. Otherwise
+/// pages are read from the first until one has nothing new or changed (the
+/// newest records come first), plus every page past the last known page count
+/// in case the list grows at the end.
+pub async fn pagintated_sync<
+    T: for<'a> Deserialize<'a> + PagintatedResponse<D>,
+    D: for<'a> Deserialize<'a> + ApiRecord,
+>(
+    fetcher: &Fetcher,
+    settings: &Fetch,
+    api_url: &str,
+    corpus: &mut Corpus,
+    full: bool,
+) -> Result<Changes> {
+    let kind = D::KINDS[0];
+    let sweep = corpus.start_sweep(kind, full)?;
+    if sweep.resumed {
+        println!(
+            "{api_url}: resuming, {} pages already stored",
+            sweep.done.len()
+        );
+    }
 
-    let first_url = format!("{api_url}?page=1");
-    let first: T = client.get(first_url).send().await?.json().await?;
+    let first: T = fetcher.page(api_url, 1).await?;
+    let pages = u32::from(first.pagintation().pages);
 
-    let Pagination { count, pages } = first.pagintation();
-    let pages = *pages;
+    let mut changes = Changes::default();
+    let mut stored = sweep.done.clone();
+    let mut walk_head = true;
 
-    let mut all_data = Vec::with_capacity(*count as usize);
-    all_data.extend(first.page());
+    if stored.insert(1) {
+        let first = store_page(corpus, settings, kind, &sweep, 1, first.page())?;
+        println!("{api_url} 1/{pages}");
 
-    let pages_stream = futures::stream::iter(2..=pages).map(|page| {
-        let client = client.clone();
-        let url = format!("{api_url}?page={page}");
+        changes += first;
+        walk_head = sweep.full || !first.is_empty();
+    }
 
-        async move {
-            let resp = fetch_page_with_retries::<T>(&client, &url, 3).await?;
-            let resp = resp.page();
+    if walk_head {
+        let head: Vec<u32> = (2..=pages).filter(|page| !stored.contains(page)).collect();
+        let mut head = fetch_pages::<T, D>(fetcher, api_url, head);
 
+        while let Some((page, records)) = head.try_next().await? {
+            let page_changes = store_page(corpus, settings, kind, &sweep, page, records)?;
             println!("{api_url} {page}/{pages}");
-            Ok::<_, anyhow::Error>(resp)
+
+            stored.insert(page);
+            changes += page_changes;
+            if !sweep.full && page_changes.is_empty() {
+                break;
+            }
         }
-    });
+    }
 
-    let mut buffered = pages_stream.buffer_unordered(20);
+    let tail: Vec<u32> = (sweep.last_pages.max(1)..=pages)
+        .filter(|page| !stored.contains(page))
+        .collect();
+    let mut tail = fetch_pages::<T, D>(fetcher, api_url, tail);
 
-    while let Some(page_res) = buffered.try_next().await? {
-        all_data.extend(page_res);
+    while let Some((page, records)) = tail.try_next().await? {
+        changes += store_page(corpus, settings, kind, &sweep, page, records)?;
+        println!("{api_url} {page}/{pages}");
     }
 
-    Ok(all_data)
+    changes.deleted = corpus.finish_sweep(kind, D::KINDS, &sweep, pages)?;
+
+    Ok(changes)
 }
 
-async fn fetch_page_with_retries<T>(
-    client: &reqwest::Client,
-    url: &str,
-    max_retries: usize,
-) -> Result<T>
+/// Concurrent, but yields in page order.
+fn fetch_pages<T, D>(
+    fetcher: &Fetcher,
+    api_url: &str,
+    pages: Vec<u32>,
+) -> impl Stream<Item = Result<(u32, Vec<D>), PageError>>
 where
-    T: for<'a> Deserialize<'a>,
+    T: for<'a> Deserialize<'a> + PagintatedResponse<D>,
 {
-    let mut attempt = 0;
-    loop {
-        match client.get(url).send().await {
-            Ok(resp) => match resp.json::<T>().await {
-                Ok(json) => return Ok(json),
-                Err(err) => {
-                    attempt += 1;
-                    if attempt >= max_retries {
-                        return Err(anyhow!(
-                            "JSON parse error after {max_retries} attempts: {err}"
-                        ));
-                    }
-                }
-            },
-            Err(err) => {
-                attempt += 1;
-                if attempt >= max_retries {
-                    return Err(anyhow!(
-                        "Request failed after {max_retries} attempts: {err}"
-                    ));
+    futures::stream::iter(pages)
+        .map(move |page| async move {
+            let resp: T = fetcher.page(api_url, page).await?;
+
+            Ok((page, resp.page()))
+        })
+        .buffered(fetcher.concurrency)
+}
+
+fn store_page<D: ApiRecord>(
+    corpus: &mut Corpus,
+    settings: &Fetch,
+    kind: Kind,
+    sweep: &Sweep,
+    page: u32,
+    records: Vec<D>,
+) -> Result<Changes> {
+    let records: Vec<Record> = records
+        .into_iter()
+        .flat_map(|record| record.records(settings))
+        .collect();
+
+    corpus.apply_page(kind, sweep, page, &records)
+}
+
+/// An API item as corpus records.
+pub trait ApiRecord {
+    /// What [`ApiRecord::records`] can yield, the first names the sweep
+    const KINDS: &[Kind];
+
+    fn records(self, settings: &Fetch) -> Vec<Record>;
+}
+
+fn record(kind: Kind, id: u32) -> Record {
+    Record {
+        source: SOURCE.to_string(),
+        kind: Some(kind),
+        id: Some(id),
+        ..Default::default()
+    }
+}
+
+impl ApiRecord for Devlog {
+    const KINDS: &[Kind] = &[Kind::Devlog];
+
+    fn records(self, _settings: &Fetch) -> Vec<Record> {
+        vec![Record {
+            project_id: self.project_id,
+            slack_id: self.slack_id,
+            attachment: self.attachment.map(|attachment| attachment.to_string()),
+            text: self.text,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            ..record(Kind::Devlog, self.id)
+        }]
+    }
+}
+
+impl ApiRecord for Project {
+    // titles are swept with their projects, so turning them off drops them
+    const KINDS: &[Kind] = &[Kind::Project, Kind::Title];
+
+    fn records(self, settings: &Fetch) -> Vec<Record> {
+        let project = Record {
+            project_id: Some(self.id),
+            slack_id: self.slack_id,
+            title: self.title,
+            category: self.category,
+            readme_link: self.readme_link,
+            demo_link: self.demo_link,
+            repo_link: self.repo_link,
+            text: self.description,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            ..record(Kind::Project, self.id)
+        };
+
+        let title = match &project.title {
+            Some(title) if settings.titles && !title.trim().is_empty() => Some(Record {
+                project_id: project.project_id,
+                slack_id: project.slack_id.clone(),
+                text: title.clone(),
+                created_at: project.created_at.clone(),
+                updated_at: project.updated_at.clone(),
+                ..record(Kind::Title, self.id)
+            }),
+            _ => None,
+        };
+
+        std::iter::once(project).chain(title).collect()
+    }
+}
+
+impl ApiRecord for Comment {
+    const KINDS: &[Kind] = &[Kind::Comment];
+
+    fn records(self, _settings: &Fetch) -> Vec<Record> {
+        vec![Record {
+            devlog_id: self.devlog_id,
+            slack_id: self.slack_id,
+            text: self.text,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            ..record(Kind::Comment, self.id)
+        }]
+    }
+}
+
+impl ApiRecord for User {
+    const KINDS: &[Kind] = &[Kind::User];
+
+    fn records(self, _settings: &Fetch) -> Vec<Record> {
+        vec![Record {
+            slack_id: self.slack_id,
+            title: self.display_name,
+            text: self.bio.unwrap_or_default(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            ..record(Kind::User, self.id)
+        }]
+    }
+}
+
+/// The Summer of Making API, devlogs then projects, then comments and users
+/// when asked for, settings under `[fetch]`.
+/// Needs `JOURNEY` in `.env` unless replaying fixtures.
+pub struct SummerOfMaking {
+    settings: Fetch,
+}
+
+impl SummerOfMaking {
+    pub fn new(settings: Fetch) -> Self {
+        Self { settings }
+    }
+}
+
+impl DataSource for SummerOfMaking {
+    fn name(&self) -> &str {
+        SOURCE
+    }
+
+    fn sync<'a>(&'a self, corpus: &'a mut Corpus, full: bool) -> BoxFuture<'a, Result<Changes>> {
+        Box::pin(async move {
+            // replays don't need the cookie
+            let api_key = match self.settings.replay {
+                Some(_) => String::new(),
+                None => dotenvy::EnvLoader::new().load()?.var("JOURNEY")?,
+            };
+            let fetcher = fetcher(&self.settings, &api_key)?;
+            let api = &self.settings.api;
+
+            let mut changes = pagintated_sync::<Devlogs, Devlog>(
+                &fetcher,
+                &self.settings,
+                &format!("{api}/devlogs"),
+                corpus,
+                full,
+            )
+            .await?;
+
+            changes += pagintated_sync::<Projects, Project>(
+                &fetcher,
+                &self.settings,
+                &format!("{api}/projects"),
+                corpus,
+                full,
+            )
+            .await?;
+
+            if self.settings.comments {
+                changes += pagintated_sync::<Comments, Comment>(
+                    &fetcher,
+                    &self.settings,
+                    &format!("{api}/comments"),
+                    corpus,
+                    full,
+                )
+                .await?;
+            } else if full {
+                changes.deleted += corpus.drop_kind(Kind::Comment)?;
+            }
+
+            if self.settings.users {
+                changes += pagintated_sync::<Users, User>(
+                    &fetcher,
+                    &self.settings,
+                    &format!("{api}/users"),
+                    corpus,
+                    full,
+                )
+                .await?;
+            } else if full {
+                changes.deleted += corpus.drop_kind(Kind::User)?;
+            }
+
+            Ok(changes)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use super::*;
+    use crate::http::tests::{Reply, Server, fetcher};
+
+    /// `/devlogs`, two per page, newest first. `fail` answers that page with
+    /// a 401.
+    struct Api {
+        server: Server,
+        devlogs: Arc<Mutex<Vec<(u32, &'static str)>>>,
+        fail: Arc<AtomicU32>,
+    }
+
+    impl Api {
+        async fn start(ids: impl IntoIterator<Item = u32>) -> Self {
+            let devlogs = Arc::new(Mutex::new(ids.into_iter().map(|id| (id, "a")).collect()));
+            let fail = Arc::new(AtomicU32::new(0));
+
+            let (shared, failing) = (devlogs.clone(), fail.clone());
+            let server = Server::start(move |_, page, _| {
+                if failing.load(Ordering::SeqCst) == page {
+                    return Reply::status(401);
                 }
+
+                let devlogs: &Vec<(u32, &str)> = &shared.lock().unwrap();
+                let items: Vec<String> = devlogs
+                    .chunks(2)
+                    .nth(page as usize - 1)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|(id, updated_at)| {
+                        format!(
+                            r#"{{"id": {id}, "text": "devlog {id}", "updated_at": "{updated_at}"}}"#
+                        )
+                    })
+                    .collect();
+
+                Reply::json(format!(
+                    r#"{{"devlogs": [{}], "pagination": {{"pages": {}}}}}"#,
+                    items.join(", "),
+                    devlogs.len().div_ceil(2).max(1)
+                ))
+            })
+            .await;
+
+            Self {
+                server,
+                devlogs,
+                fail,
             }
         }
 
-        let delay = Duration::from_millis(500 * 2_u64.pow(attempt as u32 - 1));
-        println!("Retrying in {}ms", delay.as_millis());
-        tokio::time::sleep(delay).await;
+        /// Changes and the pages it asked for.
+        async fn sync(&self, corpus: &mut Corpus, full: bool) -> Result<(Changes, Vec<u32>)> {
+            self.server.log().requests.clear();
+
+            // one page at a time, so the walk stops exactly where it decides to
+            let settings = Fetch {
+                attempts: 1,
+                concurrency: 1,
+                ..Fetch::default()
+            };
+            let url = format!("{}/devlogs", self.server.url);
+            let changes = pagintated_sync::<Devlogs, Devlog>(
+                &fetcher(settings.clone()),
+                &settings,
+                &url,
+                corpus,
+                full,
+            )
+            .await?;
+
+            Ok((changes, self.server.log().pages("/devlogs")))
+        }
+
+        fn devlogs(&self) -> std::sync::MutexGuard<'_, Vec<(u32, &'static str)>> {
+            self.devlogs.lock().unwrap()
+        }
     }
-}
 
-pub async fn fetch_all(api_key: &str) -> Result<Vec<String>> {
-    let devlogs =
-        pagintated_fetch::<Devlogs, Devlog>("https://summer.hackclub.com/api/v1/devlogs", api_key)
-            .await?
+    fn corpus() -> Corpus {
+        Corpus::open(Path::new(":memory:")).unwrap()
+    }
+
+    fn counts(changes: Changes) -> (usize, usize, usize) {
+        (changes.added, changes.updated, changes.deleted)
+    }
+
+    #[tokio::test]
+    async fn walks_the_head_until_nothing_changed() {
+        let api = Api::start((1..=6).rev()).await;
+        let mut corpus = corpus();
+
+        // the first sync is full
+        let (changes, pages) = api.sync(&mut corpus, false).await.unwrap();
+        assert_eq!((counts(changes), pages), ((6, 0, 0), vec![1, 2, 3]));
+
+        // page 1 and the last known page
+        let (changes, pages) = api.sync(&mut corpus, false).await.unwrap();
+        assert_eq!((counts(changes), pages), ((0, 0, 0), vec![1, 3]));
+
+        // page 1 changed, page 2 didn't
+        api.devlogs()[0].1 = "b";
+        let (changes, pages) = api.sync(&mut corpus, false).await.unwrap();
+        assert_eq!((counts(changes), pages), ((0, 1, 0), vec![1, 2, 3]));
+    }
+
+    #[tokio::test]
+    async fn reads_pages_the_list_grew_by() {
+        let api = Api::start((1..=6).rev()).await;
+        let mut corpus = corpus();
+        api.sync(&mut corpus, false).await.unwrap();
+
+        // older records showing up at the end
+        api.devlogs().extend([(100, "a"), (101, "a"), (102, "a")]);
+        let (changes, pages) = api.sync(&mut corpus, false).await.unwrap();
+
+        assert_eq!((counts(changes), pages), ((3, 0, 0), vec![1, 3, 4, 5]));
+        assert_eq!(corpus.len().unwrap(), 9);
+    }
+
+    #[tokio::test]
+    async fn deletes_on_full_syncs_only() {
+        let api = Api::start((1..=6).rev()).await;
+        let mut corpus = corpus();
+        api.sync(&mut corpus, false).await.unwrap();
+
+        api.devlogs().retain(|&(id, _)| id != 3);
+        let (changes, _) = api.sync(&mut corpus, false).await.unwrap();
+        assert_eq!(counts(changes), (0, 0, 0));
+        assert_eq!(corpus.len().unwrap(), 6);
+
+        let (changes, pages) = api.sync(&mut corpus, true).await.unwrap();
+        assert_eq!((counts(changes), pages), ((0, 0, 1), vec![1, 2, 3]));
+        assert_eq!(corpus.len().unwrap(), 5);
+    }
+
+    #[tokio::test]
+    async fn keeps_migrated_texts() {
+        let api = Api::start((1..=6).rev()).await;
+        let mut corpus = corpus();
+        corpus.import_legacy(vec!["old".into()]).unwrap();
+
+        // the first sync is a full one
+        let (changes, _) = api.sync(&mut corpus, false).await.unwrap();
+        assert_eq!(counts(changes), (6, 0, 0));
+        let (changes, _) = api.sync(&mut corpus, true).await.unwrap();
+        assert_eq!(counts(changes), (0, 0, 0));
+
+        let texts: Vec<_> = corpus
+            .records()
+            .unwrap()
             .into_iter()
-            .map(|x| x.text);
+            .map(|r| r.text)
+            .collect();
+        assert_eq!(texts.len(), 7);
+        assert!(texts.contains(&"old".to_string()));
+    }
+
+    #[tokio::test]
+    async fn resumes_an_interrupted_sync() {
+        let api = Api::start((1..=6).rev()).await;
+        let mut corpus = corpus();
+
+        api.fail.store(3, Ordering::SeqCst);
+        let err = api.sync(&mut corpus, false).await.unwrap_err();
+        assert!(err.to_string().contains("page 3"), "{err}");
+        assert_eq!(corpus.len().unwrap(), 4);
+
+        // page 1 again for the page count, then only what's missing
+        api.fail.store(0, Ordering::SeqCst);
+        let (changes, pages) = api.sync(&mut corpus, false).await.unwrap();
+        assert_eq!((counts(changes), pages), ((2, 0, 0), vec![1, 3]));
+        assert_eq!(corpus.len().unwrap(), 6);
+
+        // and it finished, the next sync is incremental again
+        let (_, pages) = api.sync(&mut corpus, false).await.unwrap();
+        assert_eq!(pages, [1, 3]);
+    }
+
+    #[tokio::test]
+    async fn pages_in_flight_are_capped() {
+        let server = Server::start(|_, _, _| {
+            Reply::json(r#"{"devlogs": [], "pagination": {"pages": 9}}"#)
+                .delay(Duration::from_millis(50))
+        })
+        .await;
+        let fetcher = fetcher(Fetch {
+            concurrency: 3,
+            ..Fetch::default()
+        });
+
+        let pages: Vec<u32> =
+            fetch_pages::<Devlogs, Devlog>(&fetcher, &server.url, (1..=9).collect())
+                .map_ok(|(page, _)| page)
+                .try_collect()
+                .await
+                .unwrap();
+
+        assert_eq!(pages, (1..=9).collect::<Vec<_>>());
+        assert_eq!(server.log().max_in_flight, 3);
+    }
+
+    fn fixtures() -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures")
+    }
+
+    fn replaying(settings: Fetch) -> Fetch {
+        Fetch {
+            api: "http://replay".into(),
+            replay: Some(fixtures()),
+            ..settings
+        }
+    }
+
+    fn everything() -> Fetch {
+        replaying(Fetch {
+            titles: true,
+            comments: true,
+            users: true,
+            ..Fetch::default()
+        })
+    }
 
-    let projects = pagintated_fetch::<Projects, Project>(
-        "https://summer.hackclub.com/api/v1/projects",
-        api_key,
-    )
-    .await?
-    .into_iter()
-    .map(|x| x.description);
+    fn fixture<T: for<'a> Deserialize<'a>>(endpoint: &str) -> T {
+        let path = fixtures().join(endpoint).join("page-1.json");
+        serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn fixtures_deserialize_with_missing_fields() {
+        let devlogs = fixture::<Devlogs>("devlogs").page();
+        let bare = devlogs.iter().find(|devlog| devlog.id == 103).unwrap();
+        assert_eq!(bare.text, "");
+        assert!(bare.attachment.is_none() && bare.project_id.is_none());
+        assert!(bare.created_at.is_none() && bare.updated_at.is_none());
+
+        let projects = fixture::<Projects>("projects").page();
+        let untitled = projects.iter().find(|project| project.id == 12).unwrap();
+        assert!(untitled.title.is_none() && untitled.repo_link.is_none());
+        assert_eq!(untitled.description, "");
+        assert_eq!(untitled.category.as_deref(), Some("CLI"));
+
+        let comments = fixture::<Comments>("comments").page();
+        let text = |id| {
+            &comments
+                .iter()
+                .find(|comment| comment.id == id)
+                .unwrap()
+                .text
+        };
+        assert_eq!(text(501), "this is so cool");
+        assert_eq!(text(502), "how did you batch the draws?");
+        assert_eq!(text(503), "");
+
+        let users = fixture::<Users>("users").page();
+        let user = |id| users.iter().find(|user| user.id == id).unwrap();
+        assert_eq!(user(1).display_name.as_deref(), Some("user 2"));
+        assert_eq!(user(1).bio.as_deref(), Some("bio removed"));
+        assert!(user(3).display_name.is_none() && user(3).bio.is_none());
+    }
+
+    #[tokio::test]
+    async fn fetcher_replays_failures_in_order() {
+        let url = "http://replay/devlogs";
+
+        let err = fetcher(replaying(Fetch {
+            attempts: 2,
+            ..Fetch::default()
+        }))
+        .page::<Devlogs>(url, 2)
+        .await
+        .err()
+        .expect("two failures recorded");
+        assert_eq!(err.attempts, 2);
+        assert_eq!(err.cause.to_string(), "HTTP 503 Service Unavailable");
+
+        let page: Devlogs = fetcher(replaying(Fetch::default()))
+            .page(url, 2)
+            .await
+            .unwrap();
+        assert_eq!(page.pagintation().pages, 2);
+        assert_eq!(page.page().len(), 2);
+
+        let missing = fetcher(replaying(Fetch::default()))
+            .page::<Devlogs>(url, 3)
+            .await
+            .err()
+            .expect("no page 3 recorded");
+        assert!(matches!(missing.cause, crate::http::Cause::Fixture(_)));
+    }
 
-    Ok(devlogs.chain(projects).collect())
+    #[tokio::test]
+    async fn replays_every_endpoint() {
+        let mut corpus = corpus();
+
+        let changes = SummerOfMaking::new(everything())
+            .sync(&mut corpus, false)
+            .await
+            .unwrap();
+        // 7 devlogs, 3 projects, 2 titles, 3 comments, 3 users
+        assert_eq!(counts(changes), (18, 0, 0));
+
+        let records = corpus.records().unwrap();
+        let find = |kind, id| {
+            records
+                .iter()
+                .find(|record| record.kind == Some(kind) && record.id == Some(id))
+                .unwrap()
+        };
+
+        assert_eq!(find(Kind::Title, 11).text, "Tiles");
+        assert_eq!(
+            find(Kind::Comment, 502).text,
+            "how did you batch the draws?"
+        );
+        assert_eq!(find(Kind::Comment, 502).devlog_id, Some(101));
+        assert_eq!(
+            find(Kind::Devlog, 101).slack_id.as_deref(),
+            Some("U00000003")
+        );
+        assert!(find(Kind::Devlog, 101).attachment.is_some());
+        // users are stored, not scored
+        assert!(records.iter().all(|record| record.kind != Some(Kind::User)));
+        assert_eq!(corpus.len().unwrap(), 18);
+
+        let changes = SummerOfMaking::new(everything())
+            .sync(&mut corpus, false)
+            .await
+            .unwrap();
+        assert!(changes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn full_syncs_drop_what_was_turned_off() {
+        let mut corpus = corpus();
+        SummerOfMaking::new(everything())
+            .sync(&mut corpus, false)
+            .await
+            .unwrap();
+
+        let settings = replaying(Fetch {
+            titles: false,
+            comments: false,
+            users: false,
+            ..Fetch::default()
+        });
+
+        // only a full sync deletes
+        let changes = SummerOfMaking::new(settings.clone())
+            .sync(&mut corpus, false)
+            .await
+            .unwrap();
+        assert_eq!(counts(changes), (0, 0, 0));
+
+        let changes = SummerOfMaking::new(settings)
+            .sync(&mut corpus, true)
+            .await
+            .unwrap();
+        // 2 titles, 3 comments, 3 users
+        assert_eq!(counts(changes), (0, 0, 8));
+        assert_eq!(corpus.len().unwrap(), 10);
+    }
 }